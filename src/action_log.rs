@@ -0,0 +1,151 @@
+//! A bounded, in-memory record of what the app did, to retrace the steps that
+//! led to a bug. It is only written on save when the user opts in.
+
+use std::collections::VecDeque;
+
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Older entries are dropped once the log is this long.
+const CAPACITY: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Kind {
+    Run,
+    Import,
+    Edit,
+    Error,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Run => "run",
+            Self::Import => "import",
+            Self::Edit => "edit",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub kind: Kind,
+    pub message: String,
+}
+
+impl Entry {
+    /// Time of day in UTC, e.g. `14:03:27`.
+    pub fn clock(&self) -> String {
+        let secs = self.time % (24 * 60 * 60);
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.message.to_lowercase().contains(&query) || self.kind.name().contains(&query)
+    }
+}
+
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct ActionLog {
+    entries: VecDeque<Entry>,
+}
+
+impl ActionLog {
+    pub fn push(&mut self, kind: Kind, message: impl Into<String>) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        self.entries.push_back(Entry {
+            time,
+            kind,
+            message: message.into(),
+        });
+    }
+
+    /// Records an edit unless it continues the last entry, so typing is one entry.
+    pub fn edit(&mut self, message: &str) {
+        let last = self.entries.back();
+        if !last.is_some_and(|entry| entry.kind == Kind::Edit && entry.message == message) {
+            self.push(Kind::Edit, message);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &Entry> {
+        self.entries.iter()
+    }
+
+    /// One line per entry: time, kind and message, separated by tabs.
+    pub fn export(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} UTC\t{}\t{}\n",
+                    entry.clock(),
+                    entry.kind.name(),
+                    entry.message
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_are_coalesced() {
+        let mut log = ActionLog::default();
+        log.edit("Edited the code");
+        log.edit("Edited the code");
+        log.push(Kind::Run, "Ran");
+        log.edit("Edited the code");
+        let kinds: Vec<Kind> = log.entries().map(|entry| entry.kind).collect();
+        assert_eq!(kinds, [Kind::Edit, Kind::Run, Kind::Edit]);
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut log = ActionLog::default();
+        for i in 0..=CAPACITY {
+            log.push(Kind::Run, i.to_string());
+        }
+        assert_eq!(log.entries().count(), CAPACITY);
+        assert_eq!(
+            log.entries().next().map(|entry| entry.message.as_str()),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn search_and_export() {
+        let entry = Entry {
+            time: 14 * 3600 + 3 * 60 + 27,
+            kind: Kind::Error,
+            message: "Run FAILED".into(),
+        };
+        assert_eq!(entry.clock(), "14:03:27");
+        assert!(entry.matches("failed"));
+        assert!(entry.matches("err"));
+        assert!(!entry.matches("import"));
+        let log = ActionLog {
+            entries: VecDeque::from([entry]),
+        };
+        assert_eq!(log.export(), "14:03:27 UTC\terror\tRun FAILED\n");
+    }
+}
@@ -0,0 +1,277 @@
+//! Lightweight textual analysis of programs, done without parsing or running them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+/// Returns the contents of every `{{ ... }}` block with its byte offset in `code`.
+pub fn blocks(code: &str) -> Vec<(usize, &str)> {
+    let mut blocks = Vec::new();
+    let mut rest = 0;
+    while let Some(open) = code.get(rest..).and_then(|s| s.find("{{")) {
+        let start = rest + open + 2;
+        let Some(len) = code.get(start..).and_then(|s| s.find("}}")) else {
+            break;
+        };
+        if let Some(block) = code.get(start..start + len) {
+            blocks.push((start, block));
+        }
+        rest = start + len + 2;
+    }
+    blocks
+}
+
+/// Returns the code of a block with string literals and comments blanked out,
+/// so byte offsets stay the same.
+pub fn strip_literals(block: &str) -> String {
+    let mut out = String::with_capacity(block.len());
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut escaped = false;
+    for c in block.chars() {
+        let keep = if in_comment {
+            in_comment = c != '\n';
+            c == '\n'
+        } else if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            false
+        } else if c == '"' {
+            in_string = true;
+            false
+        } else if c == '#' {
+            in_comment = true;
+            false
+        } else {
+            true
+        };
+        if keep {
+            out.push(c);
+        } else {
+            // keep the byte length of the replaced character
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+    out
+}
+
+/// Byte ranges of the string literals in the blocks of `code`, quotes included.
+/// An unterminated literal ends with its block.
+pub fn string_literals(code: &str) -> Vec<Range<usize>> {
+    blocks(code)
+        .into_iter()
+        .flat_map(|(start, block)| {
+            block_literals(block)
+                .into_iter()
+                .map(move |literal| start + literal.start..start + literal.end)
+        })
+        .collect()
+}
+
+/// Byte ranges of the string literals in one block, see [`string_literals`].
+fn block_literals(block: &str) -> Vec<Range<usize>> {
+    let mut literals = Vec::new();
+    let mut open = None;
+    let mut in_comment = false;
+    let mut escaped = false;
+    for (i, c) in block.char_indices() {
+        if in_comment {
+            in_comment = c != '\n';
+        } else if let Some(from) = open {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                literals.push(from..i + 1);
+                open = None;
+            }
+        } else if c == '"' {
+            open = Some(i);
+        } else if c == '#' {
+            in_comment = true;
+        }
+    }
+    if let Some(from) = open {
+        literals.push(from..block.len());
+    }
+    literals
+}
+
+/// Names of the arrays indexed or sliced by the program, e.g. `LIST` in `LIST[:]`.
+pub fn referenced_arrays(code: &str) -> BTreeSet<String> {
+    blocks(code)
+        .into_iter()
+        .flat_map(|(_, block)| block_arrays(block))
+        .collect()
+}
+
+/// Names of the arrays indexed or sliced in one block.
+fn block_arrays(block: &str) -> BTreeSet<String> {
+    let block = strip_literals(block);
+    let mut names = BTreeSet::new();
+    let mut ident = String::new();
+    for c in block.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+            continue;
+        }
+        if c == '[' && !ident.is_empty() {
+            names.insert(ident.clone());
+        }
+        ident.clear();
+    }
+    names
+}
+
+/// Rough size of the output in bytes, estimated without running the program.
+///
+/// Text outside of blocks is counted once. A block is counted once per combination
+/// of items of the arrays it indexes, as if it looped over all of them, producing
+/// the text of its string literals, macro templates included, plus an average
+/// item of each such array every time. Comments and the rest of its code are not
+/// output and not counted.
+pub fn estimate_output(code: &str, arrays: &BTreeMap<String, Vec<String>>) -> usize {
+    let blocks = blocks(code);
+    let mut total = code
+        .len()
+        .saturating_sub(blocks.iter().map(|(_, block)| block.len() + 4).sum());
+    for (_, block) in blocks {
+        let mut iterations = 1usize;
+        let mut per_iteration: usize = block_literals(block)
+            .iter()
+            .map(|literal| literal.len().saturating_sub(2))
+            .sum();
+        for name in block_arrays(block) {
+            let Some(items) = arrays.get(&name) else {
+                continue;
+            };
+            let count = items.len();
+            iterations = iterations.saturating_mul(count);
+            let bytes: usize = items.iter().map(String::len).sum();
+            per_iteration += bytes.checked_div(count).unwrap_or(0);
+        }
+        total = total.saturating_add(iterations.saturating_mul(per_iteration));
+    }
+    total
+}
+
+/// A macro definition, `@name template`.
+pub struct Macro {
+    pub name: String,
+    pub template: String,
+    /// Byte offset of the `@` in the code.
+    pub offset: usize,
+    /// 1-based line of the definition.
+    pub line: usize,
+}
+
+/// Every macro defined in the program, in order of definition.
+pub fn macros(code: &str) -> Vec<Macro> {
+    let mut macros = Vec::new();
+    for (start, block) in blocks(code) {
+        let stripped = strip_literals(block);
+        for (at, _) in stripped.match_indices('@') {
+            let name: String = stripped
+                .get(at + 1..)
+                .unwrap_or_default()
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            if name.is_empty() {
+                continue;
+            }
+            let template = block
+                .get(at + 1 + name.len()..)
+                .and_then(|rest| rest.lines().next())
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+            let offset = start + at;
+            macros.push(Macro {
+                line: line_of(code, offset),
+                name,
+                template,
+                offset,
+            });
+        }
+    }
+    macros
+}
+
+/// Names and offsets of the `?name` macro applications that are not preceded
+/// by a definition of the macro.
+pub fn undefined_macros(code: &str) -> Vec<(String, usize)> {
+    let defined = macros(code);
+    let mut undefined = Vec::new();
+    for (start, block) in blocks(code) {
+        let stripped = strip_literals(block);
+        for (at, _) in stripped.match_indices('?') {
+            let name: String = stripped
+                .get(at + 1..)
+                .unwrap_or_default()
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            let offset = start + at;
+            if !name.is_empty() && !defined.iter().any(|m| m.name == name && m.offset < offset) {
+                undefined.push((name, offset));
+            }
+        }
+    }
+    undefined
+}
+
+/// 1-based line number of the byte `offset` in `code`.
+pub fn line_of(code: &str, offset: usize) -> usize {
+    code.get(..offset)
+        .map_or(0, |before| before.matches('\n').count())
+        + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_and_literals() {
+        let code = "a {{ \"x\" # \"not\"\n L[:] }} b {{ \"open }} {{ unclosed";
+        assert_eq!(
+            blocks(code),
+            [(4, " \"x\" # \"not\"\n L[:] "), (30, " \"open ")]
+        );
+        assert_eq!(string_literals(code), [5..8, 31..37]);
+        assert_eq!(
+            referenced_arrays(code).into_iter().collect::<Vec<_>>(),
+            ["L"]
+        );
+    }
+
+    #[test]
+    fn undefined_macros_are_applied_before_their_definition() {
+        let code = "{{ L[:] | ?late\n@late \"x\"\nL[:] | ?late | ?missing \"?quoted\" }}";
+        let names: Vec<String> = undefined_macros(code)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["late", "missing"]);
+        assert_eq!(
+            macros(code).first().map(|m| (m.name.as_str(), m.line)),
+            Some(("late", 2))
+        );
+    }
+
+    #[test]
+    fn estimate_counts_the_literals_of_a_block_per_item() {
+        let arrays = BTreeMap::from([("L".to_owned(), vec!["ab".to_owned(), "cdef".to_owned()])]);
+        // 2 bytes of text, then twice an average item of 3 bytes
+        assert_eq!(estimate_output("<>{{L[:]}}", &arrays), 2 + 2 * 3);
+        // the macro template and an average item for both items, not the comment
+        let code = "<>{{ # \"not output\"\n  @m \"<$(_)>\"\n  L[:] | ?m }}";
+        assert_eq!(estimate_output(code, &arrays), 2 + 2 * (6 + 3));
+    }
+}
@@ -1,8 +1,143 @@
 use std::collections::BTreeMap;
 use std::io::Write;
+use std::time::Duration;
 use pipa::ir::{gen_ir, dump_ir};
 use pipa::syntax::ast;
 use pipa::vm::Vm;
+use web_time::Instant;
+
+use crate::action_log::{self, ActionLog};
+use crate::analysis;
+use crate::diff::{self, Change};
+use crate::dotenv;
+use crate::examples;
+use crate::inputs::{self, Inputs, RunConfig, TrailingLine};
+use crate::markdown::{self, Block, Span};
+use crate::output;
+use crate::validation::Rule;
+
+/// How long the editor has to stay untouched before a live check runs, in seconds.
+const CHECK_DEBOUNCE: f64 = 0.5;
+/// Names the language binds inside loops and macros, see [`LOOP_VARIABLES_HELP`].
+const LOOP_VARIABLES: [&str; 3] = ["_index_", "_item_", "_"];
+const LOOP_VARIABLES_HELP: &str = "Inside loops and macros `_index_` is the index of the current item, \
+     `_item_` is the item and `_` is the result of the previous pipe stage. \
+     Constants and arrays with these names are shadowed there.";
+/// Re-runs the last program that ran without errors.
+const RERUN_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+    egui::Key::Enter,
+);
+/// Parts of the page focused by Alt+1, Alt+2 and Alt+3.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Constants,
+    Arrays,
+    Editor,
+}
+
+impl Section {
+    const ALL: [Self; 3] = [Self::Constants, Self::Arrays, Self::Editor];
+
+    fn shortcut(self) -> egui::KeyboardShortcut {
+        let key = match self {
+            Self::Constants => egui::Key::Num1,
+            Self::Arrays => egui::Key::Num2,
+            Self::Editor => egui::Key::Num3,
+        };
+        egui::KeyboardShortcut::new(egui::Modifiers::ALT, key)
+    }
+
+    /// The widget that gets the keyboard focus.
+    fn focus_id(self) -> egui::Id {
+        match self {
+            Self::Constants => egui::Id::new("new_var_name"),
+            Self::Arrays => egui::Id::new("new_array_name"),
+            Self::Editor => code_editor_id(),
+        }
+    }
+}
+
+/// Tokenizing slower than this is reported in the status bar.
+const SLOW_TOKENIZE: Duration = Duration::from_millis(50);
+/// Programs with more tokens than this are reported in the status bar.
+const MANY_TOKENS: usize = 20_000;
+/// Runs in a session without saving or exporting after which exporting is suggested.
+const EXPORT_SUGGESTION_RUNS: usize = 20;
+/// Pages narrower than this, in points, get the touch friendly way of adding constants.
+const COMPACT_WIDTH: f32 = 600.0;
+/// Estimated outputs larger than this, in bytes, are flagged before running.
+const LARGE_OUTPUT: usize = 100 * 1024 * 1024;
+
+/// Steps of the first-launch tour, the title and explanation of each highlighted part.
+const TOUR: [(&str, &str); 5] = [
+    (
+        "Editor",
+        "This is your program. Text outside of {{ }} is copied to the output as is, \
+         the code inside is executed.",
+    ),
+    (
+        "Constants",
+        "Constants are available in the program by name, e.g. \"$(name)\" in a string.",
+    ),
+    (
+        "Arrays",
+        "Every line of an array is an item. LIST[:] takes all of them, \
+         a macro applied to it runs once per item.",
+    ),
+    ("Run", "Runs the program with the current constants and arrays."),
+    (
+        "Output",
+        "The generated text appears here, the console above shows the vm state and the IR.",
+    ),
+];
+const TOUR_EDITOR: usize = 0;
+const TOUR_CONSTANTS: usize = 1;
+const TOUR_ARRAYS: usize = 2;
+const TOUR_RUN: usize = 3;
+const TOUR_OUTPUT: usize = 4;
+
+/// Where the console and output are shown.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum OutputLayout {
+    /// Below the editor, in the same scroll area.
+    Inline,
+    /// In a resizable panel on the right.
+    Side,
+    /// In a resizable panel at the bottom.
+    Bottom,
+}
+
+/// Usage numbers shown in the stats panel. They never leave this device.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct UsageStats {
+    runs: u32,
+    run_time: Duration,
+    largest_output: usize,
+    /// How often each example was loaded, by name.
+    example_loads: BTreeMap<String, u32>,
+}
+
+impl UsageStats {
+    fn record(&mut self, run_time: Duration, output: &str) {
+        self.runs += 1;
+        self.run_time += run_time;
+        self.largest_output = self.largest_output.max(output.len());
+    }
+
+    fn record_example(&mut self, name: &str) {
+        *self.example_loads.entry(name.to_owned()).or_default() += 1;
+    }
+
+    /// The most loaded example and how often it was loaded.
+    fn most_used_example(&self) -> Option<(&str, u32)> {
+        self.example_loads
+            .iter()
+            .max_by_key(|(_, loads)| **loads)
+            .map(|(name, loads)| (name.as_str(), *loads))
+    }
+}
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -12,28 +147,171 @@ pub struct App {
     new_var: (String, String),
     new_array: (String, String),
     vars: BTreeMap<String, String>,
+    vars_grid: bool,
+    /// Shows the constants in `var_order` instead of alphabetically.
+    manual_var_order: bool,
+    /// Display order of the constants, the vm does not depend on it.
+    var_order: Vec<String>,
+    /// Validation rules of the constants, checked while editing and before running.
+    var_rules: BTreeMap<String, Rule>,
+    default_var_value: String,
+    var_name_prefix: String,
     arrays: BTreeMap<String, String>,
+    /// What a blank last line of an array becomes, unless overridden in `array_trailing_lines`.
+    trailing_line: TrailingLine,
+    array_trailing_lines: BTreeMap<String, TrailingLine>,
+    show_item_numbers: bool,
+    item_numbers_base: usize,
+    strict_import: bool,
     code: String,
+    /// Markdown describing the program, see [`notes_panel`].
+    notes: String,
+    editing_notes: bool,
+    /// Makes the editor, constants and arrays read-only.
+    locked: bool,
+    live_check: bool,
+    normalize_paste: bool,
+    highlight_transformed: bool,
+    /// Leaves tabs in string literals as they are when expanding tabs.
+    keep_string_tabs: bool,
+    warn_empty_arrays: bool,
+    tour_completed: bool,
+    autosave_secs: u64,
+    persist: bool,
+    persist_stats: bool,
+    persist_log: bool,
+    suggest_export: bool,
+    /// Copy of `stats` written on save when `persist_stats` is on.
+    saved_stats: Option<UsageStats>,
+    /// Copy of `log` written on save when `persist_log` is on.
+    saved_log: Option<ActionLog>,
     console: String,
+    /// Opens the console after failed runs and collapses it after successful ones.
+    auto_console: bool,
     output: String,
+    wrap_output: bool,
+    minify_output: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    output_file: OutputFile,
+    wrap_column: usize,
+    /// Seconds between items when playing back, see [`playback_panel`].
+    playback_delay: f64,
+    diff_ir: bool,
+    key_value_view: bool,
+    key_value_separator: String,
+    output_layout: OutputLayout,
+    pin_run: bool,
+    label_output_format: bool,
+    high_contrast: bool,
+    contrast_fg: egui::Color32,
+    contrast_bg: egui::Color32,
+    diagnostic_colors: DiagnosticColors,
+    #[serde(skip)]
+    diagnostics: String,
+    #[serde(skip)]
+    warnings: Vec<Warning>,
+    #[serde(skip)]
+    notice: String,
+    #[serde(skip)]
+    summary: Option<RunSummary>,
+    #[serde(skip)]
+    last_success: Option<RunInput>,
+    #[serde(skip)]
+    reveal_diagnostics: bool,
+    #[serde(skip)]
+    tour: Tour,
+    #[serde(skip)]
+    macros: Vec<analysis::Macro>,
+    /// Selected code being moved into a new array, see [`extract_array_modal`].
+    #[serde(skip)]
+    extract: Option<Extract>,
+    /// Byte offset in the code to move the editor cursor to.
+    #[serde(skip)]
+    jump_to: Option<usize>,
+    /// Byte ranges of the placeholders of the inserted snippet still ahead,
+    /// the first one is selected, see [`examples`].
+    #[serde(skip)]
+    snippet_stops: Vec<std::ops::Range<usize>>,
+    #[serde(skip)]
+    select_stop: bool,
+    /// Values being entered for the parameters of an example, see [`example_form_modal`].
+    #[serde(skip)]
+    example_form: Option<ExampleForm>,
+    #[serde(skip)]
+    example_diff: ExampleDiff,
+    /// IR of the last run, `None` before the first one.
+    #[serde(skip)]
+    ir: Option<String>,
+    /// `ir` diffed with the IR of the previous run, computed once per run.
+    /// `None` until there are two runs to compare.
+    #[serde(skip)]
+    ir_changes: Option<Vec<(Change, String)>>,
+    #[serde(skip)]
+    last_edit: Option<f64>,
+    #[serde(skip)]
+    parse_stats: Option<ParseStats>,
+    /// Set by the `kiosk` query parameter on the web, nothing is loaded or saved.
+    #[serde(skip)]
+    kiosk: bool,
+    #[serde(skip)]
+    stats: UsageStats,
+    #[serde(skip)]
+    log: ActionLog,
+    #[serde(skip)]
+    log_query: String,
+    #[serde(skip)]
+    last_saved: Option<Instant>,
+    #[serde(skip)]
+    save_requested: bool,
+    #[serde(skip)]
+    output_cache: OutputCache,
+    #[serde(skip)]
+    output_target: String,
+    /// Byte ranges of `code` rewritten by the last run, see [`expand_tabs`].
+    #[serde(skip)]
+    transformed: Vec<std::ops::Range<usize>>,
+    /// Section to scroll into view once it is drawn.
+    #[serde(skip)]
+    reveal_section: Option<Section>,
+    /// Open state forced on the console for one frame, see `auto_console`.
+    #[serde(skip)]
+    console_open: Option<bool>,
+    /// Estimated output size in bytes, shown until the run is confirmed or cancelled.
+    #[serde(skip)]
+    estimate: Option<usize>,
+    /// The touch friendly dialog for adding a constant is open.
+    #[serde(skip)]
+    adding_var: bool,
+    /// Runs since the last save or export, see [`EXPORT_SUGGESTION_RUNS`].
+    #[serde(skip)]
+    unsaved_runs: usize,
+    /// The export suggestion was dismissed, it is shown once per session.
+    #[serde(skip)]
+    export_suggested: bool,
+    /// Constant or array being added under a taken name, see [`collision_modal`].
+    #[serde(skip)]
+    collision: Option<Collision>,
+    #[serde(skip)]
+    slice: SliceProbe,
+    #[serde(skip)]
+    playback: Playback,
+    #[serde(skip)]
+    repl_input: String,
+    #[serde(skip)]
+    repl_history: Vec<ReplEntry>,
+    #[serde(skip)]
+    transfer: String,
+    #[serde(skip)]
+    transfer_errors: Vec<String>,
+    #[serde(skip)]
+    env_path: String,
+    /// Malformed lines of the last imported `.env` file.
+    #[serde(skip)]
+    env_errors: Vec<String>,
 }
 
-impl Default for App {
-    fn default() -> Self {
-        Self {
-            scale: 1.0,
-            new_var: (String::new(), String::new()),
-            new_array: (String::new(), String::new()),
-            vars: BTreeMap::from([
-                ("name".into(), "jon".into()),
-                ("sirname".into(), "doe".into())
-            ]),
-            arrays: BTreeMap::from([
-                ("LIST".into(), "first\nsecond\nthird".into()),
-            ]),
-            console: String::new(),
-            code: String::from(
-r#"<!DOCTYPE html>
+/// Program shown on the first start.
+const DEFAULT_CODE: &str = r#"<!DOCTYPE html>
 <html>
   <head>
     <title>This is a hello page</title>
@@ -57,8 +335,107 @@ r#"<!DOCTYPE html>
         </ul>
     </div>
   </body>
-</html>"#),
+</html>"#;
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            new_var: (String::new(), String::new()),
+            new_array: (String::new(), String::new()),
+            vars: BTreeMap::from([("name".into(), "jon".into()), ("sirname".into(), "doe".into())]),
+            vars_grid: false,
+            manual_var_order: false,
+            var_order: Vec::new(),
+            var_rules: BTreeMap::new(),
+            default_var_value: String::new(),
+            var_name_prefix: String::new(),
+            arrays: BTreeMap::from([
+                ("LIST".into(), "first\nsecond\nthird".into()),
+            ]),
+            trailing_line: TrailingLine::default(),
+            array_trailing_lines: BTreeMap::new(),
+            show_item_numbers: false,
+            item_numbers_base: 0,
+            strict_import: true,
+            console: String::new(),
+            auto_console: false,
+            code: DEFAULT_CODE.into(),
+            notes: String::new(),
+            editing_notes: false,
+            locked: false,
+            live_check: true,
+            normalize_paste: false,
+            highlight_transformed: false,
+            keep_string_tabs: false,
+            warn_empty_arrays: true,
+            tour_completed: false,
+            autosave_secs: 30,
+            persist: true,
+            persist_stats: false,
+            persist_log: false,
+            suggest_export: true,
+            saved_stats: None,
+            saved_log: None,
             output: String::new(),
+            wrap_output: false,
+            minify_output: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            output_file: OutputFile::default(),
+            wrap_column: 80,
+            playback_delay: 1.0,
+            diff_ir: false,
+            key_value_view: false,
+            key_value_separator: ":".into(),
+            output_layout: OutputLayout::Inline,
+            pin_run: true,
+            label_output_format: true,
+            high_contrast: false,
+            contrast_fg: egui::Color32::WHITE,
+            contrast_bg: egui::Color32::BLACK,
+            diagnostic_colors: DiagnosticColors::default(),
+            diagnostics: String::new(),
+            warnings: Vec::new(),
+            notice: String::new(),
+            summary: None,
+            last_success: None,
+            reveal_diagnostics: false,
+            tour: Tour::default(),
+            macros: Vec::new(),
+            extract: None,
+            jump_to: None,
+            snippet_stops: Vec::new(),
+            select_stop: false,
+            example_form: None,
+            example_diff: ExampleDiff::default(),
+            ir: None,
+            ir_changes: None,
+            last_edit: None,
+            parse_stats: None,
+            kiosk: false,
+            stats: UsageStats::default(),
+            log: ActionLog::default(),
+            log_query: String::new(),
+            last_saved: None,
+            save_requested: false,
+            output_cache: OutputCache::default(),
+            output_target: String::new(),
+            transformed: Vec::new(),
+            reveal_section: None,
+            console_open: None,
+            estimate: None,
+            adding_var: false,
+            unsaved_runs: 0,
+            export_suggested: false,
+            collision: None,
+            slice: SliceProbe::default(),
+            playback: Playback::default(),
+            repl_input: String::new(),
+            repl_history: Vec::new(),
+            transfer: String::new(),
+            transfer_errors: Vec::new(),
+            env_path: String::new(),
+            env_errors: Vec::new(),
         }
     }
 }
@@ -69,33 +446,79 @@ impl App {
         // This is also where you can customize the look and feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
+        // Kiosk deployments start fresh every session: `index.html?kiosk`
+        #[cfg(target_arch = "wasm32")]
+        let kiosk = cc.integration_info.web_info.location.query_map.contains_key("kiosk");
+        #[cfg(not(target_arch = "wasm32"))]
+        let kiosk = false;
+
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
-        } else {
-            Default::default()
+        let mut app: Self = match cc.storage {
+            Some(storage) if !kiosk => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            _ => Default::default(),
+        };
+        if !app.persist {
+            app = Self::forgetful();
+        }
+        app.kiosk = kiosk;
+        app.stats = app.saved_stats.take().unwrap_or_default();
+        app.log = app.saved_log.take().unwrap_or_default();
+        // the macros are not saved, they are listed before the first check
+        app.macros = analysis::macros(&app.code);
+        if !app.tour_completed {
+            app.tour.start();
+        }
+        app
+    }
+
+    /// The default state with persistence turned off, the only thing saved when it is off.
+    fn forgetful() -> Self {
+        Self {
+            persist: false,
+            ..Default::default()
         }
     }
 }
 
 impl eframe::App for App {
-    /// Called by the framework to save state before shutdown.
+    /// Called by the framework to save state before shutdown and every [`Self::auto_save_interval`].
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        if self.kiosk {
+            return;
+        }
+        if self.persist {
+            self.saved_stats = self.persist_stats.then(|| self.stats.clone());
+            self.saved_log = self.persist_log.then(|| self.log.clone());
+            eframe::set_value(storage, eframe::APP_KEY, self);
+        } else {
+            eframe::set_value(storage, eframe::APP_KEY, &Self::forgetful());
+        }
+        self.last_saved = Some(Instant::now());
+    }
+
+    fn auto_save_interval(&self) -> Duration {
+        Duration::from_secs(self.autosave_secs)
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
         ctx.set_theme(egui::Theme::Light);
         ctx.set_pixels_per_point(self.scale);
-
+        self.diagnostic_colors.apply(ctx);
+        handle_input(self, ctx);
+        status_bar(self, ctx);
+        output_panel(self, ctx);
 
         egui::CentralPanel::default().show(&ctx, |ui| {
             egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.heading("pipa playground");
+                ui.horizontal(|ui| {
+                    ui.heading("pipa playground");
+                    diagnostics_badge(self, ui);
+                    lock_toggle(self, ui);
+                });
                 ui.separator();
                 // scale
                 ui.horizontal(|ui| {
@@ -109,139 +532,2685 @@ impl eframe::App for App {
                         let v = self.scale + 0.5;
                         self.scale = if v > 5.0 { 5.0 } else { v }
                     }
-                    ui.hyperlink_to("Examples", "https://github.com/GachiLord/pipa/tree/main/examples")
+                    ui.hyperlink_to("Examples", "https://github.com/GachiLord/pipa/tree/main/examples");
+                    ui.add_enabled_ui(!self.locked, |ui| ui.menu_button("Load example", |ui| examples_menu(self, ui)));
+                    ui.separator();
+                    if ui.button("Save now").clicked() {
+                        self.save_requested = true;
+                        self.unsaved_runs = 0;
+                    }
+                    match self.last_saved {
+                        Some(saved) => ui.weak(format!("Saved {} s ago", saved.elapsed().as_secs())),
+                        None => ui.weak("Not saved yet"),
+                    };
+                });
+                ui.collapsing("Settings", |ui| {
+                    settings_section(self, ui);
+                });
+                ui.collapsing("Usage stats", |ui| {
+                    stats_section(self, ui);
+                });
+                ui.collapsing("Action log", |ui| {
+                    log_section(self, ui);
                 });
                 // display vars
-                ui.label("Constants:");
-                vars_editor(self, ui);
+                let vars = ui.scope(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Constants:").on_hover_text(LOOP_VARIABLES_HELP);
+                        ui.checkbox(&mut self.vars_grid, "Compact grid");
+                        ui.checkbox(&mut self.manual_var_order, "Manual order")
+                            .on_hover_text("Keeps the constants in the order they were added, drag ☰ to reorder");
+                    });
+                    ui.add_enabled_ui(!self.locked, |ui| vars_editor(self, ui));
+                });
+                self.tour.target(ui, TOUR_CONSTANTS, vars.response.rect);
+                reveal_section(self, ui, Section::Constants, vars.response.rect);
                 // arrays
                 ui.separator();
-                ui.label("Arrays(separated by a newline):");
-                arrays_editor(self, ui);
-                ui.separator();
-                // editor
-                let editor = egui::TextEdit::multiline(&mut self.code)
-                    .code_editor()
-                    .desired_width(f32::INFINITY)
-                    .desired_rows(10);
-                ui.add(editor);
-                // execution
-                if ui.button("Run").clicked() {
-                    run_vm(self);
-                }
-                // console 
-                ui.collapsing("Console", |ui| {
-                    ui.code(&self.console);
+                let arrays = ui.scope(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Arrays(separated by a newline):").on_hover_text(LOOP_VARIABLES_HELP);
+                        ui.checkbox(&mut self.show_item_numbers, "Item numbers");
+                        if self.show_item_numbers {
+                            ui.radio_value(&mut self.item_numbers_base, 0, "0-based");
+                            ui.radio_value(&mut self.item_numbers_base, 1, "1-based");
+                        }
+                    });
+                    ui.add_enabled_ui(!self.locked, |ui| arrays_editor(self, ui));
+                });
+                self.tour.target(ui, TOUR_ARRAYS, arrays.response.rect);
+                reveal_section(self, ui, Section::Arrays, arrays.response.rect);
+                ui.collapsing("Import/Export JSON", |ui| {
+                    transfer_editor(self, ui);
+                });
+                ui.collapsing("Import .env", |ui| {
+                    ui.add_enabled_ui(!self.locked, |ui| env_import(self, ui));
                 });
                 ui.separator();
-                ui.label("Output:");
-                ui.code(&self.output);
+                tools_section(self, ui);
+                editor_section(self, ui);
+                if self.output_layout == OutputLayout::Inline {
+                    results_section(self, ui);
+                }
             });
         });
+        add_var_button(self, ctx);
+        add_var_modal(self, ctx);
+        collision_modal(self, ctx);
+        example_form_modal(self, ctx);
+        if self.tour.show(ctx) {
+            self.tour_completed = true;
+        }
+        if std::mem::take(&mut self.save_requested) {
+            if let Some(storage) = frame.storage_mut() {
+                self.save(storage);
+                storage.flush();
+            }
+        }
     }
 }
 
-fn vars_editor(state: &mut App, ui: &mut egui::Ui) {
-    let mut to_delete = Vec::with_capacity(state.vars.len());
-    for (key, value) in state.vars.iter_mut() {
-        ui.horizontal(|ui| {
-            ui.label(key);
-            ui.add(egui::TextEdit::multiline(value).desired_rows(1));
-            if ui.button("Remove").clicked() {
-                to_delete.push(key.to_owned());
+/// Colors of errors, warnings and notices. Errors and warnings are drawn
+/// with the egui visuals' colors, so they are applied there.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct DiagnosticColors {
+    error: egui::Color32,
+    warning: egui::Color32,
+    info: egui::Color32,
+}
+
+impl Default for DiagnosticColors {
+    fn default() -> Self {
+        let visuals = egui::Visuals::light();
+        Self {
+            error: visuals.error_fg_color,
+            warning: visuals.warn_fg_color,
+            info: visuals.weak_text_color(),
+        }
+    }
+}
+
+impl DiagnosticColors {
+    fn apply(self, ctx: &egui::Context) {
+        let visuals = &ctx.style().visuals;
+        if visuals.error_fg_color != self.error || visuals.warn_fg_color != self.warning {
+            ctx.all_styles_mut(|style| {
+                style.visuals.error_fg_color = self.error;
+                style.visuals.warn_fg_color = self.warning;
+            });
+        }
+    }
+}
+
+/// State of the guided tour over the main parts of the page, see [`TOUR`].
+#[derive(Default)]
+struct Tour {
+    step: Option<usize>,
+    /// Where the current step's part was drawn this frame.
+    target: Option<egui::Rect>,
+    /// Scroll the current step's part into view once it is drawn.
+    reveal: bool,
+}
+
+impl Tour {
+    fn start(&mut self) {
+        self.go_to(Some(0));
+    }
+
+    fn go_to(&mut self, step: Option<usize>) {
+        self.step = step;
+        self.target = None;
+        self.reveal = true;
+    }
+
+    /// Records where the part of `step` was drawn.
+    fn target(&mut self, ui: &egui::Ui, step: usize, rect: egui::Rect) {
+        if self.step != Some(step) {
+            return;
+        }
+        self.target = Some(rect);
+        if self.reveal {
+            self.reveal = false;
+            ui.scroll_to_rect(rect, Some(egui::Align::Center));
+        }
+    }
+
+    /// Highlights the current step, returns true when the tour has been finished or skipped.
+    fn show(&mut self, ctx: &egui::Context) -> bool {
+        let Some(step) = self.step else {
+            return false;
+        };
+        let (Some(rect), Some((title, text))) = (self.target, TOUR.get(step)) else {
+            return false;
+        };
+        let highlight = ctx.style().visuals.selection.stroke;
+        ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("tour_highlight")))
+            .rect_stroke(rect.expand(4.0), 4.0, highlight, egui::StrokeKind::Outside);
+
+        let mut finished = false;
+        egui::Area::new(egui::Id::new("tour"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(rect.left_bottom() + egui::vec2(0.0, 8.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(280.0);
+                    ui.strong(format!("{title} ({}/{})", step + 1, TOUR.len()));
+                    ui.label(*text);
+                    ui.horizontal(|ui| {
+                        let last = step + 1 == TOUR.len();
+                        if ui.button(if last { "Done" } else { "Next" }).clicked() {
+                            finished = last;
+                            self.go_to((!last).then_some(step + 1));
+                        }
+                        if !last && ui.button("Skip tour").clicked() {
+                            finished = true;
+                            self.go_to(None);
+                        }
+                    });
+                });
+            });
+        finished
+    }
+}
+
+/// Work that has to happen before the widgets consume this frame's input.
+fn handle_input(state: &mut App, ctx: &egui::Context) {
+    // live check, restarted by every edit so stale checks never run
+    if let Some(edited_at) = state.last_edit {
+        let idle = ctx.input(|i| i.time) - edited_at;
+        if idle >= CHECK_DEBOUNCE {
+            state.last_edit = None;
+            apply_check(state);
+        } else {
+            ctx.request_repaint_after(Duration::from_secs_f64(CHECK_DEBOUNCE - idle));
+        }
+    }
+    if state.normalize_paste && ctx.memory(|m| m.has_focus(code_editor_id())) {
+        ctx.input_mut(|i| {
+            for event in &mut i.events {
+                if let egui::Event::Paste(text) = event {
+                    *text = normalize_punctuation(text);
+                }
             }
         });
     }
-    for var in to_delete {
-        state.vars.remove(&var);
+    if ctx.input_mut(|i| i.consume_shortcut(&RERUN_SHORTCUT)) {
+        rerun_last_success(state);
     }
-    // add vars
+    let dropped = if state.locked {
+        Vec::new()
+    } else {
+        ctx.input(|i| i.raw.dropped_files.clone())
+    };
+    for file in dropped {
+        let is_env = file.path.as_deref().map_or(file.name.as_str(), |path| {
+            path.file_name().and_then(|name| name.to_str()).unwrap_or_default()
+        });
+        if !is_env.starts_with(".env") && !is_env.ends_with(".env") {
+            continue;
+        }
+        let src = match (&file.bytes, &file.path) {
+            (Some(bytes), _) => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            (None, Some(path)) => std::fs::read_to_string(path).map_err(|e| e.to_string()),
+            (None, None) => continue,
+        };
+        import_env(state, src, is_env);
+    }
+    for section in Section::ALL {
+        if ctx.input_mut(|i| i.consume_shortcut(&section.shortcut())) {
+            ctx.memory_mut(|m| m.request_focus(section.focus_id()));
+            state.reveal_section = Some(section);
+        }
+    }
+}
+
+/// Scrolls to `rect` if `section` was just jumped to.
+fn reveal_section(state: &mut App, ui: &egui::Ui, section: Section, rect: egui::Rect) {
+    if state.reveal_section.take_if(|jumped| *jumped == section).is_some() {
+        ui.scroll_to_rect(rect, Some(egui::Align::TOP));
+    }
+}
+
+fn status_bar(state: &App, ctx: &egui::Context) {
+    if let Some(stats) = state.parse_stats.as_ref().filter(|stats| stats.is_slow()) {
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!(
+                    "Large input: {} tokens, tokenized in {:.1} ms",
+                    stats.tokens,
+                    stats.tokenize_time.as_secs_f64() * 1000.0
+                ),
+            );
+        });
+    }
+}
+
+/// Locks the editor and inputs with a click, unlocking takes a double click.
+fn lock_toggle(state: &mut App, ui: &mut egui::Ui) {
+    if state.locked {
+        ui.colored_label(ui.visuals().warn_fg_color, "🔒 Locked");
+        if ui
+            .small_button("Unlock")
+            .on_hover_text("Double-click to allow editing the code, constants and arrays again")
+            .double_clicked()
+        {
+            state.locked = false;
+        }
+    } else if ui
+        .small_button("🔒 Lock")
+        .on_hover_text("Makes the code, constants and arrays read-only, e.g. while presenting")
+        .clicked()
+    {
+        state.locked = true;
+    }
+}
+
+/// Error and warning counts of the last check or run, clicking scrolls to the first one.
+fn diagnostics_badge(state: &mut App, ui: &mut egui::Ui) {
+    let errors = usize::from(!state.diagnostics.is_empty());
+    let warnings = state.warnings.len();
+    if errors == 0 && warnings == 0 {
+        return;
+    }
+    let color = if errors > 0 {
+        ui.visuals().error_fg_color
+    } else {
+        ui.visuals().warn_fg_color
+    };
+    let text = format!(
+        "{errors} {}, {warnings} {}",
+        if errors == 1 { "error" } else { "errors" },
+        if warnings == 1 { "warning" } else { "warnings" },
+    );
+    if ui
+        .add(egui::Button::new(egui::RichText::new(text).color(color)).small())
+        .on_hover_text("Show the first one")
+        .clicked()
+    {
+        state.reveal_diagnostics = true;
+    }
+}
+
+fn stats_section(state: &mut App, ui: &mut egui::Ui) {
+    ui.weak("These numbers are kept on this device only and are never sent anywhere.");
+    let stats = &state.stats;
+    egui::Grid::new("usage_stats").num_columns(2).show(ui, |ui| {
+        ui.label("Runs:");
+        ui.label(stats.runs.to_string());
+        ui.end_row();
+        ui.label("Average run time:");
+        if stats.runs > 0 {
+            let average = stats.run_time / stats.runs;
+            ui.label(format!("{:.2} ms", average.as_secs_f64() * 1000.0));
+        } else {
+            ui.label("-");
+        }
+        ui.end_row();
+        ui.label("Largest output:");
+        ui.label(format!("{} bytes", stats.largest_output));
+        ui.end_row();
+        ui.label("Most used example:");
+        match stats.most_used_example() {
+            Some((name, 1)) => ui.label(format!("{name}, loaded once")),
+            Some((name, loads)) => ui.label(format!("{name}, loaded {loads} times")),
+            None => ui.label("-"),
+        };
+        ui.end_row();
+    });
     ui.horizontal(|ui| {
-        ui.add(egui::TextEdit::singleline(&mut state.new_var.0).hint_text("Name"));
-        ui.add(egui::TextEdit::multiline(&mut state.new_var.1).desired_rows(1).hint_text("Value"));
-        if ui.button("Add").clicked() {
-            let key: String = state.new_var.0.drain(..).collect();
-            state.vars.insert(key, state.new_var.1.drain(..).collect());
+        ui.checkbox(&mut state.persist_stats, "Keep between sessions");
+        if ui.button("Reset").clicked() {
+            state.stats = UsageStats::default();
         }
     });
 }
 
-fn arrays_editor(state: &mut App, ui: &mut egui::Ui) {
-    let mut to_delete = Vec::with_capacity(state.arrays.len());
-    for (key, value) in state.arrays.iter_mut() {
-        ui.horizontal(|ui| {
-            ui.label(key);
-            ui.add(egui::TextEdit::multiline(value).desired_rows(1));
-            if ui.button("Remove").clicked() {
-                to_delete.push(key.to_owned());
+/// Runs, imports, edits and errors of this session, newest first.
+fn log_section(state: &mut App, ui: &mut egui::Ui) {
+    ui.weak("A record of what the app did, to retrace the steps to a bug. It stays on this device.");
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.log_query).hint_text("Search"));
+        if ui.add_enabled(!state.log.is_empty(), egui::Button::new("Copy")).clicked() {
+            ui.ctx().copy_text(state.log.export());
+            state.notice = "Copied the action log".into();
+        }
+        if ui.add_enabled(!state.log.is_empty(), egui::Button::new("Clear")).clicked() {
+            state.log.clear();
+        }
+        ui.checkbox(&mut state.persist_log, "Keep between sessions");
+    });
+    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+        egui::Grid::new("action_log").num_columns(3).striped(true).show(ui, |ui| {
+            for entry in state.log.entries().rev().filter(|entry| entry.matches(&state.log_query)) {
+                ui.weak(entry.clock());
+                if entry.kind == action_log::Kind::Error {
+                    ui.colored_label(ui.visuals().error_fg_color, entry.kind.name());
+                } else {
+                    ui.label(entry.kind.name());
+                }
+                ui.label(&entry.message);
+                ui.end_row();
             }
         });
+    });
+}
+
+fn settings_section(state: &mut App, ui: &mut egui::Ui) {
+    ui.checkbox(&mut state.normalize_paste, "Replace smart quotes and dashes in pasted code")
+        .on_hover_text("Turns “ ” ‘ ’ – — … pasted into the editor into their ASCII equivalents");
+    ui.checkbox(&mut state.auto_console, "Open the console only after failed runs")
+        .on_hover_text("Expands the console when a run fails and collapses it when a run succeeds");
+    ui.checkbox(&mut state.keep_string_tabs, "Keep tabs in string literals")
+        .on_hover_text("Tabs are expanded to four spaces before parsing, except inside \"...\" when this is on");
+    ui.checkbox(&mut state.suggest_export, "Suggest exporting after many runs")
+        .on_hover_text(format!(
+            "Shows a reminder once per session after {EXPORT_SUGGESTION_RUNS} runs without saving or exporting"
+        ));
+    ui.checkbox(&mut state.highlight_transformed, "Highlight code changed before parsing")
+        .on_hover_text("Marks tabs that are expanded to spaces on run, and the spaces the last run expanded");
+    if state.kiosk {
+        ui.weak("Kiosk mode: nothing is saved between sessions");
+    } else {
+        ui.checkbox(&mut state.persist, "Remember the state between sessions")
+            .on_hover_text("When off, every session starts from the defaults");
     }
-    for var in to_delete {
-        state.arrays.remove(&var);
-    }
-    // add vars
     ui.horizontal(|ui| {
-        ui.add(egui::TextEdit::singleline(&mut state.new_array.0).hint_text("Name"));
-        ui.add(egui::TextEdit::multiline(&mut state.new_array.1).desired_rows(1).hint_text("Values"));
-        if ui.button("Add").clicked() {
-            state.arrays.insert(state.new_array.0.drain(..).collect(), state.new_array.1.drain(..).collect());
+        ui.label("Autosave every");
+        ui.add(egui::DragValue::new(&mut state.autosave_secs).range(5..=600).suffix(" s"));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Output:");
+        ui.radio_value(&mut state.output_layout, OutputLayout::Inline, "Below the editor");
+        ui.radio_value(&mut state.output_layout, OutputLayout::Side, "Right panel");
+        ui.radio_value(&mut state.output_layout, OutputLayout::Bottom, "Bottom panel");
+        if state.output_layout != OutputLayout::Inline {
+            ui.checkbox(&mut state.pin_run, "Run button in the panel");
+        }
+    });
+    ui.checkbox(&mut state.label_output_format, "Label the output with its format (HTML, JSON, ...)");
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut state.high_contrast, "High-contrast results");
+        if state.high_contrast {
+            ui.label("Text:");
+            ui.color_edit_button_srgba(&mut state.contrast_fg);
+            ui.label("Background:");
+            ui.color_edit_button_srgba(&mut state.contrast_bg);
+        }
+    });
+    ui.horizontal(|ui| {
+        let colors = &mut state.diagnostic_colors;
+        ui.label("Diagnostic colors: errors");
+        ui.color_edit_button_srgba(&mut colors.error);
+        ui.label("warnings");
+        ui.color_edit_button_srgba(&mut colors.warning);
+        ui.label("notices");
+        ui.color_edit_button_srgba(&mut colors.info);
+        if ui.button("Reset").clicked() {
+            *colors = DiagnosticColors::default();
         }
     });
+    if ui.button("Replay tour").clicked() {
+        state.tour.start();
+    }
+    ui.horizontal(|ui| {
+        ui.label("Default value of new constants:");
+        ui.text_edit_singleline(&mut state.default_var_value);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Name prefix of new constants:");
+        ui.text_edit_singleline(&mut state.var_name_prefix)
+            .on_hover_text("Constants added without a name are called <prefix>1, <prefix>2, ...");
+    });
+    ui.checkbox(&mut state.warn_empty_arrays, "Warn about loops over empty arrays");
+    ui.horizontal(|ui| {
+        ui.label("A blank last line of an array is");
+        for trailing in TrailingLine::ALL {
+            ui.selectable_value(&mut state.trailing_line, trailing, trailing.label());
+        }
+    })
+    .response
+    .on_hover_text("Lines of an array are its items, this decides whether the vm gets an empty last item");
 }
 
-fn run_vm(state: &mut App) {
-    state.code = state.code.replace("\t", "    ");
-    let mut output = Vec::new();
-    // tokenize + lex
-    let tokens = match ast(&state.code) {
-        Ok(r) => r, 
-        Err(e) => { 
-            e.write_message(&mut output, "index.pipa", &state.code).unwrap();
-            state.output = String::from_utf8(output).unwrap();
-            return;
-        }
+/// Shows the console and output in their own panel, so they stay visible while scrolling the editor.
+fn output_panel(state: &mut App, ctx: &egui::Context) {
+    let contents = |state: &mut App, ui: &mut egui::Ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if state.pin_run && ui.button("Run").clicked() {
+                run_vm(state);
+            }
+            results_section(state, ui);
+        });
     };
+    match state.output_layout {
+        OutputLayout::Inline => {}
+        OutputLayout::Side => {
+            egui::SidePanel::right("output_panel")
+                .resizable(true)
+                .default_width(360.0)
+                .show(ctx, |ui| contents(state, ui));
+        }
+        OutputLayout::Bottom => {
+            egui::TopBottomPanel::bottom("output_panel")
+                .resizable(true)
+                .default_height(240.0)
+                .show(ctx, |ui| contents(state, ui));
+        }
+    }
+}
 
-    // ir
-    let ir = match gen_ir(&state.code, tokens) {
-        Ok(ir) => ir,
-        Err(e) => { 
-            e.write_message(&mut output, "index.pipa", &state.code).unwrap();
-            state.output = String::from_utf8(output).unwrap();
-            return;
+fn code_editor_id() -> egui::Id {
+    egui::Id::new("code_editor")
+}
+
+/// Replaces typographic quotes, dashes and ellipses with their ASCII equivalents.
+fn normalize_punctuation(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => out.push('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => out.push('"'),
+            '\u{2013}' | '\u{2014}' => out.push('-'),
+            '\u{2026}' => out.push_str("..."),
+            '\u{00A0}' => out.push(' '),
+            c => out.push(c),
         }
-    };
-    // convert vars
-    let mut vars = BTreeMap::new();
-    let mut arrays = BTreeMap::new();
+    }
+    out
+}
 
-    for (key, value) in state.vars.clone() {
-        vars.insert(key.into(), value.into());
+/// Lays out `code` like the code editor does, with the tabs [`expand_tabs`] would
+/// replace and the `transformed` ranges on a highlighted background.
+/// The job is cached, so the code is only scanned again after it changed.
+fn transformed_job(
+    ui: &egui::Ui,
+    code: &str,
+    transformed: &[std::ops::Range<usize>],
+    keep_string_tabs: bool,
+) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let color = ui.visuals().widgets.inactive.text_color();
+    let highlight = ui.visuals().warn_fg_color.gamma_multiply(0.3);
+    ui.memory_mut(|mem| {
+        mem.caches
+            .cache::<TransformedCache>()
+            .get((&font_id, color, highlight, code, transformed, keep_string_tabs))
+    })
+}
+
+type TransformedCache = egui::cache::FrameCache<egui::text::LayoutJob, TransformedLayout>;
+
+/// Key of [`TransformedCache`]: font, text and highlight color, code, transformed
+/// ranges and whether tabs in string literals are kept.
+type TransformedKey<'a> = (
+    &'a egui::FontId,
+    egui::Color32,
+    egui::Color32,
+    &'a str,
+    &'a [std::ops::Range<usize>],
+    bool,
+);
+
+#[derive(Default)]
+struct TransformedLayout;
+
+impl egui::cache::ComputerMut<TransformedKey<'_>, egui::text::LayoutJob> for TransformedLayout {
+    fn compute(&mut self, key: TransformedKey<'_>) -> egui::text::LayoutJob {
+        let (font_id, color, highlight, code, transformed, keep_string_tabs) = key;
+        let kept = if keep_string_tabs {
+            analysis::string_literals(code)
+        } else {
+            Vec::new()
+        };
+        let mut job = egui::text::LayoutJob::default();
+        let mut append = |text: &str, highlighted: bool| {
+            let format = egui::TextFormat {
+                font_id: font_id.clone(),
+                color,
+                background: if highlighted { highlight } else { egui::Color32::TRANSPARENT },
+                ..Default::default()
+            };
+            job.append(text, 0.0, format);
+        };
+        // both lists of ranges are sorted, so each is walked once
+        let (mut kept, mut transformed) = (kept.iter().peekable(), transformed.iter().peekable());
+        let mut start = 0;
+        let mut highlighted = false;
+        for (i, c) in code.char_indices() {
+            while kept.next_if(|range| range.end <= i).is_some() {}
+            while transformed.next_if(|range| range.end <= i).is_some() {}
+            let in_literal = kept.peek().is_some_and(|range| range.contains(&i));
+            let changed = (c == '\t' && !in_literal) || transformed.peek().is_some_and(|range| range.contains(&i));
+            if changed != highlighted {
+                append(code.get(start..i).unwrap_or_default(), highlighted);
+                start = i;
+                highlighted = changed;
+            }
+        }
+        append(code.get(start..).unwrap_or_default(), highlighted);
+        job
     }
+}
 
-    for (key, value) in state.arrays.clone() {
-        arrays.insert(key.into(), value.lines().map(|s| s.into()).collect());
+fn editor_section(state: &mut App, ui: &mut egui::Ui) {
+    next_snippet_stop(state, ui);
+    let len = state.code.len();
+    let transformed = &state.transformed;
+    let keep_string_tabs = state.keep_string_tabs;
+    let mut layouter = |ui: &egui::Ui, code: &dyn egui::TextBuffer, wrap_width: f32| {
+        let mut job = transformed_job(ui, code.as_str(), transformed, keep_string_tabs);
+        job.wrap.max_width = wrap_width;
+        ui.fonts_mut(|fonts| fonts.layout_job(job))
+    };
+    let mut editor = egui::TextEdit::multiline(&mut state.code)
+        .interactive(!state.locked)
+        .code_editor()
+        .desired_width(f32::INFINITY)
+        .desired_rows(10);
+    if state.highlight_transformed {
+        editor = editor.layouter(&mut layouter);
+    }
+    let mut editor = editor.id(code_editor_id()).show(ui);
+    follow_snippet_stops(state, ui, &mut editor, len);
+    if let Some(offset) = state.jump_to.take() {
+        let cursor = egui::text::CCursor::new(state.code.get(..offset).map_or(0, |s| s.chars().count()));
+        editor
+            .state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+        editor.state.clone().store(ui.ctx(), editor.response.id);
+        editor.response.request_focus();
+        let rect = editor.galley.pos_from_cursor(cursor).translate(editor.galley_pos.to_vec2());
+        ui.scroll_to_rect(rect, Some(egui::Align::Center));
+    }
+    let cursor = editor.state.cursor.char_range().map(|range| range.as_sorted_char_range());
+    let selection = cursor.clone().filter(|range| !range.is_empty());
+    let editor = editor.response;
+    editor.context_menu(|ui| {
+        let enabled = selection.is_some() && !state.locked;
+        let button = ui.add_enabled(enabled, egui::Button::new("Move selection to a new array"));
+        if let Some(range) = selection.filter(|_| button.clicked()) {
+            state.extract = Some(Extract {
+                range: byte_offset(&state.code, range.start)..byte_offset(&state.code, range.end),
+                name: String::new(),
+            });
+            ui.close();
+        }
+        ui.add_enabled_ui(!state.locked, |ui| {
+            ui.menu_button("Insert snippet", |ui| snippet_menu(state, ui, cursor.map_or(0, |range| range.start)));
+        });
+    });
+    extract_array_modal(state, ui.ctx());
+    estimate_modal(state, ui.ctx());
+    if editor.changed() {
+        // the offsets no longer match the code
+        state.transformed.clear();
+        state.log.edit("Edited the code");
+        if state.live_check {
+            state.last_edit = Some(ui.input(|i| i.time));
+        }
+    }
+    state.tour.target(ui, TOUR_EDITOR, editor.rect);
+    reveal_section(state, ui, Section::Editor, editor.rect);
+    diagnostics_section(state, ui);
+    // execution
+    ui.horizontal(|ui| {
+        let run = ui.button("Run");
+        state.tour.target(ui, TOUR_RUN, run.rect);
+        if run.clicked() {
+            run_vm(state);
+        }
+        let rerun = ui
+            .add_enabled(state.last_success.is_some(), egui::Button::new("Run last working"))
+            .on_hover_text(format!(
+                "Runs the last version that ran without errors, keeping your edits ({})",
+                ui.ctx().format_shortcut(&RERUN_SHORTCUT)
+            ));
+        if rerun.clicked() {
+            rerun_last_success(state);
+        }
+        if ui
+            .button("Estimate")
+            .on_hover_text("Roughly estimates the output size without running")
+            .clicked()
+        {
+            state.estimate = Some(analysis::estimate_output(&state.code, &run_arrays(state)));
+        }
+        if ui.button("Check").clicked() {
+            state.last_edit = None;
+            apply_check(state);
+        }
+        ui.checkbox(&mut state.live_check, "Check while typing");
+        if ui.button("Clear all results").clicked() {
+            state.output.clear();
+            output_changed(state);
+            state.console.clear();
+            state.diagnostics.clear();
+            state.warnings.clear();
+            state.notice.clear();
+            state.summary = None;
+        }
+    });
+    if !state.notice.is_empty() {
+        ui.colored_label(state.diagnostic_colors.info, &state.notice);
     }
+    export_suggestion(state, ui);
+}
 
-    // run
-    let mut vm = Vm::new(vars, arrays);
+/// Suggests exporting the program once it has been run many times without it.
+fn export_suggestion(state: &mut App, ui: &mut egui::Ui) {
+    if !state.suggest_export || state.export_suggested || state.unsaved_runs < EXPORT_SUGGESTION_RUNS {
+        return;
+    }
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.label(format!(
+            "You have run this {} times without saving, consider exporting it in \"Import/Export JSON\" \
+             or bookmarking the page to keep a copy.",
+            state.unsaved_runs
+        ));
+        ui.horizontal(|ui| {
+            if ui.button("Dismiss").clicked() {
+                state.export_suggested = true;
+            }
+            if ui.button("Don't suggest again").clicked() {
+                state.export_suggested = true;
+                state.suggest_export = false;
+            }
+        });
+    });
+}
 
-    match vm.run(&mut output, &ir) {
-        Ok(_) => {
-        },
-        Err(e) => {
-            dbg!(e);
+/// The compile error and warnings of the last run or check.
+fn diagnostics_section(state: &mut App, ui: &mut egui::Ui) {
+    if (!state.diagnostics.is_empty() || !state.warnings.is_empty())
+        && ui.small_button("Copy diagnostics").clicked()
+    {
+        let text = std::iter::once(state.diagnostics.trim_end())
+            .chain(state.warnings.iter().map(|warning| warning.message.trim_end()))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        ui.ctx().copy_text(text);
+        state.notice = "Copied the diagnostics".into();
+    }
+    let mut first = (!state.diagnostics.is_empty()).then(|| {
+        let color = ui.visuals().error_fg_color;
+        result_label(state, ui, &state.diagnostics, Some(color))
+    });
+    let mut jump = None;
+    for warning in &state.warnings {
+        let color = ui.visuals().warn_fg_color;
+        let response = result_label(state, ui, &warning.message, Some(color));
+        if let Some(offset) = warning.offset {
+            let line = analysis::line_of(&state.code, offset);
+            if ui.link(format!("Go to line {line}")).clicked() {
+                jump = Some(offset);
+            }
         }
+        first.get_or_insert(response);
     }
-    
-    // fill console
-    let mut console = Vec::new();
-    vm.dump_state(&mut console).unwrap();
-    write!(&mut console, "\n").unwrap();
-    dump_ir(&mut console, &ir).unwrap();
+    if jump.is_some() {
+        state.jump_to = jump;
+    }
+    if let Some(first) = first.filter(|_| state.reveal_diagnostics) {
+        first.scroll_to_me(Some(egui::Align::Center));
+    }
+    state.reveal_diagnostics = false;
+}
+
+/// Selected lines of code to move into a new array.
+struct Extract {
+    /// Byte range of the selection in the code.
+    range: std::ops::Range<usize>,
+    name: String,
+}
 
-    // save changes
-    state.output = String::from_utf8(output).unwrap();
-    state.console = String::from_utf8(console).unwrap();
+/// Asks for the name of the array the selection is moved to, then adds it and replaces
+/// the selection with `NAME[:]`.
+fn extract_array_modal(state: &mut App, ctx: &egui::Context) {
+    let Some(extract) = &mut state.extract else {
+        return;
+    };
+    let taken = state.arrays.contains_key(&extract.name);
+    let mut confirmed = false;
+    let modal = egui::Modal::new(egui::Id::new("extract_array")).show(ctx, |ui| {
+        ui.label("Name of the new array:");
+        let name = ui.text_edit_singleline(&mut extract.name);
+        name.request_focus();
+        if taken {
+            ui.colored_label(ui.visuals().error_fg_color, "An array with this name already exists");
+        }
+        ui.horizontal(|ui| {
+            let valid = !extract.name.is_empty() && !taken;
+            let enter = name.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            confirmed = ui.add_enabled(valid, egui::Button::new("Create")).clicked() || (valid && enter);
+            if ui.button("Cancel").clicked() {
+                ui.close();
+            }
+        });
+    });
+    if confirmed {
+        if let Some(Extract { range, name }) = state.extract.take() {
+            let items: Vec<&str> = state.code.get(range.clone()).unwrap_or_default().lines().collect();
+            state.arrays.insert(name.clone(), items.join("\n"));
+            let reference = if in_block(&state.code, range.start) {
+                format!("{name}[:]")
+            } else {
+                format!("{{{{ {name}[:] }}}}")
+            };
+            state.code.replace_range(range, &reference);
+            state.transformed.clear();
+            if state.live_check {
+                state.last_edit = Some(ctx.input(|i| i.time));
+            }
+            state.log.push(action_log::Kind::Edit, format!("Moved the selection to the array `{name}`"));
+        }
+    } else if modal.should_close() {
+        state.extract = None;
+    }
+}
+
+/// Shows the estimated output size and asks whether to run.
+fn estimate_modal(state: &mut App, ctx: &egui::Context) {
+    let Some(estimate) = state.estimate else {
+        return;
+    };
+    let mut run = false;
+    let modal = egui::Modal::new(egui::Id::new("estimate")).show(ctx, |ui| {
+        ui.label(format!("The output will be roughly {}.", format_size(estimate)));
+        if estimate > LARGE_OUTPUT {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ This is very large, running may take long or run out of memory",
+            );
+        }
+        ui.weak("Loops are assumed to go over every item of the arrays they index.");
+        ui.horizontal(|ui| {
+            run = ui.button("Run").clicked();
+            if ui.button("Cancel").clicked() {
+                ui.close();
+            }
+        });
+    });
+    if run || modal.should_close() {
+        state.estimate = None;
+    }
+    if run {
+        run_vm(state);
+    }
+}
+
+/// Size in bytes in the largest fitting unit, e.g. `1.5 MB`.
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{size:.1} {unit}")
+}
+
+/// Byte offset of the `index`th character of `text`.
+fn byte_offset(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map_or(text.len(), |(offset, _)| offset)
+}
+
+fn snippet_menu(state: &mut App, ui: &mut egui::Ui, cursor: usize) {
+    for category in examples::categories() {
+        ui.menu_button(category, |ui| {
+            for snippet in examples::SNIPPETS.iter().filter(|snippet| snippet.category == category) {
+                if ui.button(snippet.name).clicked() {
+                    insert_snippet(state, snippet, byte_offset(&state.code, cursor));
+                    if state.live_check {
+                        state.last_edit = Some(ui.input(|i| i.time));
+                    }
+                    ui.close();
+                }
+            }
+        });
+    }
+}
+
+/// Whether the byte offset `at` is inside a `{{ }}` block of `code`.
+fn in_block(code: &str, at: usize) -> bool {
+    analysis::blocks(code)
+        .iter()
+        .any(|(start, block)| (*start..=start + block.len()).contains(&at))
+}
+
+/// Inserts the snippet at the byte offset `at`, in a new `{{ }}` block unless
+/// it is inside one, and selects its first placeholder.
+fn insert_snippet(state: &mut App, snippet: &examples::Snippet, at: usize) {
+    let (code, stops) = examples::expand(snippet.code);
+    let (open, close) = if in_block(&state.code, at) { ("", "") } else { ("{{ ", " }}") };
+    state.code.insert_str(at, &format!("{open}{code}{close}"));
+    let offset = at + open.len();
+    state.snippet_stops = stops
+        .into_iter()
+        .map(|stop| stop.start + offset..stop.end + offset)
+        .collect();
+    state.select_stop = true;
+    state.transformed.clear();
+    state.log.push(action_log::Kind::Edit, format!("Inserted the {} snippet", snippet.name));
+}
+
+/// Moves on to the next placeholder of the inserted snippet when Tab is pressed
+/// in the editor. Without placeholders Tab is left to the editor.
+fn next_snippet_stop(state: &mut App, ui: &egui::Ui) {
+    if state.snippet_stops.is_empty() || !ui.ctx().memory(|m| m.has_focus(code_editor_id())) {
+        return;
+    }
+    if ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+        state.snippet_stops.remove(0);
+        state.select_stop = !state.snippet_stops.is_empty();
+    }
+}
+
+/// Selects the current placeholder when asked to and keeps the ones after it in
+/// place while it is typed over. `len` is the length of the code before the edit.
+fn follow_snippet_stops(state: &mut App, ui: &egui::Ui, editor: &mut egui::text_edit::TextEditOutput, len: usize) {
+    if state.snippet_stops.is_empty() {
+        return;
+    }
+    if editor.response.lost_focus() {
+        state.snippet_stops.clear();
+        return;
+    }
+    if editor.response.changed() {
+        let shift = |offset: usize| (offset + state.code.len()).saturating_sub(len);
+        for stop in state.snippet_stops.iter_mut().skip(1) {
+            *stop = shift(stop.start)..shift(stop.end);
+        }
+    }
+    if std::mem::take(&mut state.select_stop)
+        && let Some(stop) = state.snippet_stops.first()
+    {
+        let char_index = |offset: usize| state.code.get(..offset).map_or(0, |s| s.chars().count());
+        let range = egui::text::CCursorRange::two(
+            egui::text::CCursor::new(char_index(stop.start)),
+            egui::text::CCursor::new(char_index(stop.end)),
+        );
+        editor.state.cursor.set_char_range(Some(range));
+        editor.state.clone().store(ui.ctx(), editor.response.id);
+        editor.response.request_focus();
+    }
+}
+
+/// An example waiting for the values of its parameters.
+struct ExampleForm {
+    /// Index in [`examples::EXAMPLES`].
+    example: usize,
+    values: Vec<String>,
+}
+
+fn examples_menu(state: &mut App, ui: &mut egui::Ui) {
+    ui.weak("Replaces the code, constants and arrays");
+    for (i, example) in examples::EXAMPLES.iter().enumerate() {
+        if ui.button(example.name).clicked() {
+            if example.params.is_empty() {
+                load_example(state, i, &[]);
+            } else {
+                state.example_form = Some(ExampleForm {
+                    example: i,
+                    values: vec![String::new(); example.params.len()],
+                });
+            }
+            ui.close();
+        }
+    }
+}
+
+/// Asks for the parameters of an example before loading it. Blank values and
+/// skipping the form load the defaults.
+fn example_form_modal(state: &mut App, ctx: &egui::Context) {
+    let Some(form) = &mut state.example_form else {
+        return;
+    };
+    let index = form.example;
+    let Some(example) = examples::EXAMPLES.get(index) else {
+        state.example_form = None;
+        return;
+    };
+    let mut load = None;
+    let modal = egui::Modal::new(egui::Id::new("example_form")).show(ctx, |ui| {
+        ui.heading(example.name);
+        egui::Grid::new("example_params").num_columns(2).show(ui, |ui| {
+            for (param, value) in example.params.iter().zip(&mut form.values) {
+                ui.label(param.prompt);
+                ui.add(egui::TextEdit::singleline(value).hint_text(param.default));
+                ui.end_row();
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Load").clicked() {
+                load = Some(std::mem::take(&mut form.values));
+            }
+            if ui.button("Skip").on_hover_text("Loads the example with the defaults").clicked() {
+                load = Some(Vec::new());
+            }
+            if ui.button("Cancel").clicked() {
+                ui.close();
+            }
+        });
+    });
+    if let Some(values) = load {
+        load_example(state, index, &values);
+        state.example_form = None;
+    } else if modal.should_close() {
+        state.example_form = None;
+    }
+}
+
+fn load_example(state: &mut App, index: usize, values: &[String]) {
+    let Some(example) = examples::EXAMPLES.get(index) else {
+        return;
+    };
+    let entries = |entries: &[(&str, &str)]| {
+        entries
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), examples::fill(value, example.params, values)))
+            .collect()
+    };
+    state.code = examples::fill_code(example.code, example.params, values);
+    state.vars = entries(example.vars);
+    state.arrays = entries(example.arrays);
+    state.transformed.clear();
+    state.example_diff = ExampleDiff {
+        example: Some(index),
+        loaded: Some((index, values.to_vec())),
+        ..ExampleDiff::default()
+    };
+    state.stats.record_example(example.name);
+    state.notice = format!("Loaded the {} example", example.name);
+    state.log.push(action_log::Kind::Import, state.notice.clone());
+}
+
+/// Collapsible panels for describing and exploring the program.
+fn tools_section(state: &mut App, ui: &mut egui::Ui) {
+    ui.collapsing("Notes", |ui| {
+        notes_panel(state, ui);
+    });
+    ui.collapsing("Macros", |ui| {
+        macros_panel(state, ui);
+    });
+    ui.collapsing("Slices", |ui| {
+        slice_panel(state, ui);
+    });
+    ui.collapsing("REPL", |ui| {
+        repl_panel(state, ui);
+    });
+    ui.collapsing("Play back items", |ui| {
+        playback_panel(state, ui);
+    });
+    ui.collapsing("Compare with example", |ui| {
+        example_diff_panel(state, ui);
+    });
+}
+
+/// Example the code is compared with in [`example_diff_panel`].
+#[derive(Default)]
+struct ExampleDiff {
+    /// Index in [`examples::EXAMPLES`].
+    example: Option<usize>,
+    /// Index and parameter values of the last loaded example, it is compared
+    /// as loaded while other examples are compared with their defaults.
+    loaded: Option<(usize, Vec<String>)>,
+    /// Code `changes` were computed for, `None` if they are out of date.
+    code: Option<String>,
+    /// The example diffed with the code.
+    changes: Vec<(Change, String)>,
+}
+
+/// Shows what changed in the code since it was an example, read-only.
+fn example_diff_panel(state: &mut App, ui: &mut egui::Ui) {
+    let compared = &mut state.example_diff;
+    let selected = compared.example.and_then(|i| examples::EXAMPLES.get(i));
+    egui::ComboBox::from_id_salt("compared_example")
+        .selected_text(selected.map_or("Example", |example| example.name))
+        .show_ui(ui, |ui| {
+            for (i, example) in examples::EXAMPLES.iter().enumerate() {
+                if ui.selectable_label(compared.example == Some(i), example.name).clicked() {
+                    compared.example = Some(i);
+                    compared.code = None;
+                }
+            }
+        });
+    let Some(index) = compared.example else {
+        return;
+    };
+    let Some(example) = examples::EXAMPLES.get(index) else {
+        return;
+    };
+    if compared.code.as_ref() != Some(&state.code) {
+        let values = match &compared.loaded {
+            Some((loaded, values)) if *loaded == index => values.as_slice(),
+            _ => &[],
+        };
+        let original = examples::fill_code(example.code, example.params, values);
+        compared.changes = diff::lines(&original, &state.code)
+            .into_iter()
+            .map(|(change, line)| (change, line.to_owned()))
+            .collect();
+        compared.code = Some(state.code.clone());
+    }
+    if compared.changes.iter().all(|(change, _)| *change == Change::Same) {
+        ui.weak(format!("The code is the same as the {} example", example.name));
+    } else {
+        diff_lines(ui, &compared.changes);
+    }
+}
+
+fn notes_panel(state: &mut App, ui: &mut egui::Ui) {
+    ui.checkbox(&mut state.editing_notes, "Edit")
+        .on_hover_text("Supports # headings, - lists, ``` code blocks, `code`, **strong** and *emphasis*");
+    if state.editing_notes {
+        ui.add(
+            egui::TextEdit::multiline(&mut state.notes)
+                .desired_width(f32::INFINITY)
+                .desired_rows(4)
+                .hint_text("What this program demonstrates, in markdown"),
+        );
+    } else if state.notes.trim().is_empty() {
+        ui.weak("No notes yet");
+    } else {
+        for block in markdown::parse(&state.notes) {
+            match block {
+                Block::Heading(level, title) => {
+                    let size = match level {
+                        1 => 22.0,
+                        2 => 18.0,
+                        _ => 15.0,
+                    };
+                    inline(ui, title, |text| text.size(size).strong());
+                }
+                Block::Item(spans) => {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("•");
+                        inline(ui, spans, |text| text);
+                    });
+                }
+                Block::Code(code) => {
+                    ui.code(code);
+                }
+                Block::Paragraph(spans) => inline(ui, spans, |text| text),
+            }
+        }
+    }
+}
+
+/// Shows formatted text, with `style` applied to every span.
+fn inline(ui: &mut egui::Ui, spans: Vec<Span>, style: impl Fn(egui::RichText) -> egui::RichText) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for span in spans {
+            let text = match span {
+                Span::Text(text) => egui::RichText::new(text),
+                Span::Strong(text) => egui::RichText::new(text).strong(),
+                Span::Emphasis(text) => egui::RichText::new(text).italics(),
+                Span::Code(text) => egui::RichText::new(text).code(),
+            };
+            ui.label(style(text));
+        }
+    });
+}
+
+fn macros_panel(state: &mut App, ui: &mut egui::Ui) {
+    if state.macros.is_empty() {
+        ui.weak("No macros defined");
+        return;
+    }
+    egui::Grid::new("macros").num_columns(2).striped(true).show(ui, |ui| {
+        for m in &state.macros {
+            let link = ui
+                .link(format!("@{}", m.name))
+                .on_hover_text(format!("Go to line {}", m.line));
+            if link.clicked() {
+                state.jump_to = Some(m.offset);
+            }
+            ui.monospace(&m.template);
+            ui.end_row();
+        }
+    });
+}
+
+/// Array and slice tried out in [`slice_panel`], with the items it resolved to.
+#[derive(Default)]
+struct SliceProbe {
+    array: String,
+    slice: String,
+    result: Option<Result<Vec<String>, String>>,
+    /// Value and trailing line setting of the array `result` was resolved with.
+    resolved_for: Option<(String, TrailingLine)>,
+}
+
+/// Lists the items a slice of an array resolves to, evaluated by the vm.
+fn slice_panel(state: &mut App, ui: &mut egui::Ui) {
+    if state.arrays.is_empty() {
+        ui.weak("No arrays to slice");
+        return;
+    }
+    let probe = &mut state.slice;
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("slice_array")
+            .selected_text(if probe.array.is_empty() { "Array" } else { probe.array.as_str() })
+            .show_ui(ui, |ui| {
+                for name in state.arrays.keys() {
+                    changed |= ui.selectable_value(&mut probe.array, name.clone(), name).changed();
+                }
+            });
+        ui.monospace("[");
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut probe.slice)
+                    .code_editor()
+                    .desired_width(80.0)
+                    .hint_text("1:3"),
+            )
+            .changed();
+        ui.monospace("]");
+    });
+    let name = &state.slice.array;
+    let array = state.arrays.get(name).map(|value| (value, trailing_line(state, name)));
+    let stale = state
+        .slice
+        .resolved_for
+        .as_ref()
+        .is_some_and(|(value, trailing)| array != Some((value, *trailing)));
+    if (changed || stale) && !state.slice.array.is_empty() {
+        let result = resolve_slice(state, &state.slice.array, &state.slice.slice);
+        state.slice.result = Some(result);
+        state.slice.resolved_for = state
+            .arrays
+            .get(&state.slice.array)
+            .map(|value| (value.clone(), trailing_line(state, &state.slice.array)));
+    }
+    match &state.slice.result {
+        Some(Ok(items)) if items.is_empty() => {
+            ui.weak("No items");
+        }
+        Some(Ok(items)) => {
+            egui::Grid::new("slice_items").num_columns(2).striped(true).show(ui, |ui| {
+                for (i, item) in items.iter().enumerate() {
+                    ui.weak(i.to_string());
+                    ui.monospace(item);
+                    ui.end_row();
+                }
+            });
+        }
+        Some(Err(error)) => {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        None => {}
+    }
+}
+
+/// Runs `array[slice]` on its own, one item per output line.
+fn resolve_slice(state: &App, array: &str, slice: &str) -> Result<Vec<String>, String> {
+    let result = execute(&RunInput {
+        code: format!("{{{{ {array}[{slice}] | \"$(_item_)\\n\" }}}}"),
+        vars: state.vars.clone(),
+        arrays: run_arrays(state),
+    });
+    if !result.diagnostics.is_empty() {
+        Err(result.diagnostics)
+    } else if let Some(error) = result.runtime_error {
+        Err(error)
+    } else {
+        Ok(result.output.lines().map(String::from).collect())
+    }
+}
+
+/// Output of the program run with only the first `items` items of an array.
+#[derive(Default)]
+struct Playback {
+    array: String,
+    items: usize,
+    playing: bool,
+    /// Input time at which the next item is added while playing.
+    next_at: f64,
+    output: String,
+    /// `output` diffed with the output of the previous item.
+    changes: Vec<(Change, String)>,
+    /// Why the last run failed, the output of the run before it is kept.
+    error: Option<String>,
+}
+
+/// Shows how the output grows as the program gets one more item of an array at a time.
+fn playback_panel(state: &mut App, ui: &mut egui::Ui) {
+    if state.arrays.is_empty() {
+        ui.weak("No arrays to play back");
+        return;
+    }
+    let total = playback_items(state).len();
+    let mut go_to = None;
+    ui.horizontal(|ui| {
+        let playback = &mut state.playback;
+        egui::ComboBox::from_id_salt("playback_array")
+            .selected_text(if playback.array.is_empty() { "Array" } else { playback.array.as_str() })
+            .show_ui(ui, |ui| {
+                for name in state.arrays.keys() {
+                    if ui.selectable_value(&mut playback.array, name.clone(), name).changed() {
+                        go_to = Some(0);
+                    }
+                }
+            });
+        if ui.button("⏮").on_hover_text("Back to no items").clicked() {
+            go_to = Some(0);
+        }
+        let play = if playback.playing { "⏸ Pause" } else { "▶ Play" };
+        if ui.add_enabled(total > 0, egui::Button::new(play)).clicked() {
+            if !playback.playing && playback.items >= total {
+                go_to = Some(0);
+            }
+            playback.playing = !playback.playing;
+            playback.next_at = ui.input(|i| i.time) + state.playback_delay;
+        }
+        if ui.add_enabled(playback.items < total, egui::Button::new("Step")).clicked() {
+            go_to = Some(playback.items + 1);
+        }
+        ui.add(
+            egui::DragValue::new(&mut state.playback_delay)
+                .range(0.1..=5.0)
+                .speed(0.05)
+                .suffix(" s per item"),
+        );
+    });
+    if state.playback.playing {
+        let now = ui.input(|i| i.time);
+        if go_to.is_none() && now >= state.playback.next_at {
+            go_to = Some(state.playback.items + 1);
+            state.playback.next_at = now + state.playback_delay;
+        }
+        ui.ctx()
+            .request_repaint_after(Duration::from_secs_f64((state.playback.next_at - now).max(0.0)));
+    }
+    if let Some(items) = go_to.filter(|items| *items <= total) {
+        play_back(state, items);
+    }
+    if state.playback.items >= total {
+        state.playback.playing = false;
+    }
+    if state.playback.array.is_empty() {
+        return;
+    }
+    let items = playback_items(state);
+    let item = state.playback.items.checked_sub(1).and_then(|i| items.get(i));
+    ui.label(match item {
+        Some(item) => format!("Item {} of {total}: {item}", state.playback.items),
+        None => format!("No items of {total}"),
+    });
+    if let Some(error) = &state.playback.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+        return;
+    }
+    // lines produced by the last item are highlighted
+    for (change, line) in &state.playback.changes {
+        let text = egui::RichText::new(line).monospace();
+        match change {
+            Change::Same => {
+                ui.label(text);
+            }
+            Change::Added => {
+                ui.label(text.color(egui::Color32::DARK_GREEN));
+            }
+            Change::Removed => {}
+        }
+    }
+}
+
+/// Items of the played back array.
+fn playback_items(state: &App) -> Vec<&str> {
+    let name = &state.playback.array;
+    state
+        .arrays
+        .get(name)
+        .map(|value| inputs::array_items(value, trailing_line(state, name)))
+        .unwrap_or_default()
+}
+
+/// Runs the program with the first `items` items of the played back array.
+fn play_back(state: &mut App, items: usize) {
+    let mut arrays = run_arrays(state);
+    if let Some(array) = arrays.get_mut(&state.playback.array) {
+        array.truncate(items);
+    }
+    let result = execute(&RunInput {
+        code: expand_tabs(&state.code, state.keep_string_tabs).0,
+        vars: state.vars.clone(),
+        arrays,
+    });
+    let playback = &mut state.playback;
+    playback.items = items;
+    playback.error = if !result.diagnostics.is_empty() {
+        Some(result.diagnostics)
+    } else {
+        result.runtime_error
+    };
+    if playback.error.is_some() {
+        playback.playing = false;
+        return;
+    }
+    let previous = if items == 0 { "" } else { playback.output.as_str() };
+    playback.changes = diff::lines(previous, &result.output)
+        .into_iter()
+        .map(|(change, line)| (change, line.to_owned()))
+        .collect();
+    playback.output = result.output;
+}
+
+/// An expression evaluated in the REPL and what it produced.
+struct ReplEntry {
+    input: String,
+    result: Result<String, String>,
+}
+
+/// Evaluates single expressions against the current constants and arrays,
+/// independently of the program in the editor.
+fn repl_panel(state: &mut App, ui: &mut egui::Ui) {
+    for entry in &state.repl_history {
+        ui.monospace(format!("> {}", entry.input));
+        match &entry.result {
+            Ok(output) => result_label(state, ui, output, None),
+            Err(error) => result_label(state, ui, error, Some(ui.visuals().error_fg_color)),
+        };
+    }
+    ui.horizontal(|ui| {
+        let input = ui.add(
+            egui::TextEdit::singleline(&mut state.repl_input)
+                .code_editor()
+                .hint_text("Expression, e.g. \"$(name)\""),
+        );
+        let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if submitted && !state.repl_input.trim().is_empty() {
+            let expression = std::mem::take(&mut state.repl_input);
+            state.repl_history.push(evaluate(state, expression));
+            input.request_focus();
+        }
+        if ui.button("Clear").clicked() {
+            state.repl_history.clear();
+        }
+    });
+}
+
+/// Runs `expression` as a program of its own, wrapping it in a block unless it has one.
+fn evaluate(state: &App, expression: String) -> ReplEntry {
+    let code = if expression.contains("{{") {
+        expression.clone()
+    } else {
+        format!("{{{{ {expression} }}}}")
+    };
+    let result = execute(&RunInput {
+        code,
+        vars: state.vars.clone(),
+        arrays: run_arrays(state),
+    });
+    let result = if !result.diagnostics.is_empty() {
+        Err(result.diagnostics)
+    } else if let Some(error) = result.runtime_error {
+        Err(error)
+    } else {
+        Ok(result.output)
+    };
+    ReplEntry {
+        input: expression,
+        result,
+    }
+}
+
+fn results_section(state: &mut App, ui: &mut egui::Ui) {
+    // console
+    let console = egui::CollapsingHeader::new("Console").open(state.console_open.take());
+    console.show(ui, |ui| {
+        ui.horizontal(|ui| {
+            if ui.button("Clear").clicked() {
+                state.console.clear();
+            }
+            ui.checkbox(&mut state.diff_ir, "Diff IR with the previous run");
+        });
+        if state.diff_ir {
+            ir_diff(state, ui);
+        } else {
+            result_label(state, ui, &state.console, None);
+        }
+    });
+    ui.separator();
+    run_summary(state, ui);
+    ui.horizontal(|ui| {
+        ui.label("Output:");
+        if ui.button("Clear").clicked() {
+            state.output.clear();
+            output_changed(state);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if output_format(state) == output::Format::Html
+            && crate::clipboard::html_copy_supported()
+            && ui
+                .button("Copy as HTML")
+                .on_hover_text("Copies the output as HTML, rich text editors paste it rendered")
+                .clicked()
+        {
+            minify_output(state, output::Format::Html);
+            crate::clipboard::copy_html(shown_output(state));
+        }
+        ui.checkbox(&mut state.minify_output, "Minify")
+            .on_hover_text("Removes insignificant whitespace from HTML, XML and JSON output");
+        ui.checkbox(&mut state.wrap_output, "Wrap at column")
+            .on_hover_text("Hard-wraps the shown output, the program is not affected");
+        if state.wrap_output {
+            ui.add(egui::DragValue::new(&mut state.wrap_column).range(10..=500));
+        }
+        ui.checkbox(&mut state.key_value_view, "Key/value table")
+            .on_hover_text("Shows the output as a table if every line is a key and a value");
+        if state.key_value_view {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.key_value_separator)
+                    .desired_width(24.0)
+                    .hint_text("sep"),
+            );
+        }
+    });
+    // only the output of a successful run is worth keeping
+    let ran = matches!(state.summary, Some(RunSummary::Ran { .. }));
+    ui.add_enabled_ui(!state.locked && ran, |ui| save_output_row(state, ui));
+    #[cfg(not(target_arch = "wasm32"))]
+    output_file_row(state, ui);
+    let output = output_view(state, ui);
+    state.tour.target(ui, TOUR_OUTPUT, output.rect);
+}
+
+/// The output as a key/value table or as text.
+fn output_view(state: &mut App, ui: &mut egui::Ui) -> egui::Response {
+    let cache = &mut state.output_cache;
+    if state.key_value_view
+        && cache.key_values.as_ref().is_none_or(|(separator, _)| *separator != state.key_value_separator)
+    {
+        let pairs = output::key_values(&state.output, &state.key_value_separator)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+        cache.key_values = Some((state.key_value_separator.clone(), pairs));
+    }
+    let pairs = state
+        .key_value_view
+        .then(|| state.output_cache.key_values.as_ref().map(|(_, pairs)| pairs))
+        .flatten()
+        .filter(|pairs| !pairs.is_empty());
+    if let Some(pairs) = pairs {
+        egui::Grid::new("output_key_values")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (key, value) in pairs {
+                    ui.strong(key);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            })
+            .response
+    } else {
+        let format = output_format(state);
+        minify_output(state, format);
+        let text = if state.wrap_output {
+            let key = (state.minify_output, state.wrap_column);
+            if state.output_cache.wrapped.as_ref().is_none_or(|(wrapped, _)| *wrapped != key) {
+                let wrapped = output::wrap(shown_output(state), state.wrap_column);
+                state.output_cache.wrapped = Some((key, wrapped));
+            }
+            state.output_cache.wrapped.as_ref().map_or("", |(_, text)| text.as_str())
+        } else {
+            shown_output(state)
+        };
+        if state.label_output_format && format != output::Format::Text {
+            egui::Frame::group(ui.style())
+                .show(ui, |ui| {
+                    ui.small(format.name());
+                    result_label(state, ui, text, None);
+                })
+                .response
+        } else {
+            result_label(state, ui, text, None)
+        }
+    }
+}
+
+/// What is computed from the output to show it, once per output.
+#[derive(Default)]
+struct OutputCache {
+    /// Sniffed format of the output, `None` until it is needed.
+    format: Option<output::Format>,
+    /// The output minified, `None` until it is shown minified.
+    minified: Option<String>,
+    /// The shown output wrapped, with whether it was minified and the column.
+    wrapped: Option<((bool, usize), String)>,
+    /// The output split into keys and values, with the separator it was split
+    /// at. No pairs if it is not a key/value table.
+    key_values: Option<(String, Vec<(String, String)>)>,
+}
+
+/// Forgets what was computed from the previous output.
+fn output_changed(state: &mut App) {
+    state.output_cache = OutputCache::default();
+}
+
+/// Sniffed format of the output.
+fn output_format(state: &mut App) -> output::Format {
+    *state.output_cache.format.get_or_insert_with(|| output::Format::sniff(&state.output))
+}
+
+/// Minifies the output of `format` if enabled and not minified yet.
+fn minify_output(state: &mut App, format: output::Format) {
+    if state.minify_output && state.output_cache.minified.is_none() {
+        state.output_cache.minified = Some(output::minify(&state.output, format).into_owned());
+    }
+}
+
+/// The output, minified if enabled, see [`minify_output`].
+fn shown_output(state: &App) -> &str {
+    match &state.output_cache.minified {
+        Some(minified) if state.minify_output => minified,
+        _ => &state.output,
+    }
+}
+
+/// File every successful run writes its output to.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct OutputFile {
+    path: String,
+    enabled: bool,
+    /// Enabling waits for the user to confirm overwriting the non-empty file.
+    #[serde(skip)]
+    confirming: bool,
+    #[serde(skip)]
+    error: Option<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl OutputFile {
+    fn write(&mut self, output: &str) {
+        if self.enabled {
+            self.error = std::fs::write(&self.path, output).err().map(|e| e.to_string());
+        }
+    }
+}
+
+/// Binds the output to a file, asking first if enabling it would overwrite one.
+#[cfg(not(target_arch = "wasm32"))]
+fn output_file_row(state: &mut App, ui: &mut egui::Ui) {
+    let file = &mut state.output_file;
+    ui.horizontal(|ui| {
+        let mut enabled = file.enabled;
+        let toggle = ui
+            .checkbox(&mut enabled, "Write output to")
+            .on_hover_text("Every successful run writes the output to this file");
+        let path = ui.add_enabled(
+            !file.enabled,
+            egui::TextEdit::singleline(&mut file.path).hint_text("Path, e.g. out/index.html"),
+        );
+        if path.changed() {
+            file.error = None;
+        }
+        if toggle.changed() {
+            let non_empty = std::fs::metadata(&file.path).is_ok_and(|meta| meta.len() > 0);
+            file.confirming = enabled && non_empty;
+            file.enabled = enabled && !non_empty && !file.path.is_empty();
+        }
+    });
+    if let Some(error) = &file.error {
+        ui.colored_label(ui.visuals().error_fg_color, format!("Could not write the output: {error}"));
+    }
+    if !file.confirming {
+        return;
+    }
+    let mut overwrite = false;
+    let modal = egui::Modal::new(egui::Id::new("output_file")).show(ui.ctx(), |ui| {
+        ui.label(format!("`{}` is not empty, runs will overwrite it.", file.path));
+        ui.horizontal(|ui| {
+            overwrite = ui.button("Overwrite").clicked();
+            if ui.button("Cancel").clicked() {
+                ui.close();
+            }
+        });
+    });
+    if overwrite || modal.should_close() {
+        file.confirming = false;
+        file.enabled = overwrite;
+    }
+}
+
+/// Stores the output as a new array or constant.
+fn save_output_row(state: &mut App, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Save output as");
+        ui.add(
+            egui::TextEdit::singleline(&mut state.output_target)
+                .desired_width(120.0)
+                .hint_text("Name"),
+        );
+        if ui.button("Array").on_hover_text("One item per line").clicked() {
+            let name = new_entry_name(&state.arrays, &state.output_target, "OUTPUT");
+            let trailing = state.trailing_line;
+            let items: Vec<String> = inputs::array_items(&state.output, trailing).into_iter().map(String::from).collect();
+            state.arrays.insert(name.clone(), inputs::array_text(&items, trailing));
+            state.notice = format!("Saved the output as array `{name}`");
+            state.output_target.clear();
+        }
+        if ui.button("Constant").clicked() {
+            let name = new_entry_name(&state.vars, &state.output_target, "output");
+            state.vars.insert(name.clone(), state.output.clone());
+            state.notice = format!("Saved the output as constant `{name}`");
+            state.output_target.clear();
+        }
+    });
+}
+
+/// Shows whether the last run succeeded, with its time and output size or the failure.
+fn run_summary(state: &App, ui: &mut egui::Ui) {
+    match &state.summary {
+        Some(RunSummary::Ran { time, lines }) => {
+            ui.colored_label(
+                egui::Color32::DARK_GREEN,
+                format!(
+                    "✔ ran in {:.1} ms, {lines} {}",
+                    time.as_secs_f64() * 1000.0,
+                    if *lines == 1 { "line" } else { "lines" }
+                ),
+            );
+        }
+        Some(RunSummary::Failed(reason)) => {
+            ui.colored_label(ui.visuals().error_fg_color, format!("✖ {reason}"));
+        }
+        None => {}
+    }
+}
+
+/// Shows the output, console or a diagnostic, in the high-contrast colors if enabled.
+fn result_label(state: &App, ui: &mut egui::Ui, text: &str, color: Option<egui::Color32>) -> egui::Response {
+    if state.high_contrast {
+        return egui::Frame::new()
+            .fill(state.contrast_bg)
+            .inner_margin(egui::Margin::same(4))
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(text).monospace().color(state.contrast_fg))
+            })
+            .inner;
+    }
+    match color {
+        Some(color) => ui.label(egui::RichText::new(text).monospace().color(color)),
+        None => ui.code(text),
+    }
+}
+
+fn ir_diff(state: &App, ui: &mut egui::Ui) {
+    let Some(changes) = &state.ir_changes else {
+        ui.weak("Run the program again to compare its IR with this run");
+        return;
+    };
+    if changes.iter().all(|(change, _)| *change == Change::Same) {
+        ui.weak("The IR has not changed since the previous run");
+        return;
+    }
+    diff_lines(ui, changes);
+}
+
+/// Lists diffed lines, marking the added and removed ones.
+fn diff_lines(ui: &mut egui::Ui, changes: &[(Change, String)]) {
+    for (change, line) in changes {
+        let (prefix, color) = match *change {
+            Change::Same => (' ', ui.visuals().weak_text_color()),
+            Change::Added => ('+', egui::Color32::DARK_GREEN),
+            Change::Removed => ('-', ui.visuals().error_fg_color),
+        };
+        ui.label(egui::RichText::new(format!("{prefix} {line}")).monospace().color(color));
+    }
+}
+
+fn vars_editor(state: &mut App, ui: &mut egui::Ui) {
+    let order = if state.manual_var_order {
+        sync_var_order(state);
+        state.var_order.clone()
+    } else {
+        state.vars.keys().cloned().collect()
+    };
+    let mut to_delete = Vec::with_capacity(state.vars.len());
+    let mut moved = None;
+    if state.vars_grid {
+        egui::Grid::new("vars_grid")
+            .num_columns(3)
+            .spacing([4.0, 2.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, key) in order.iter().enumerate() {
+                    let Some(value) = state.vars.get_mut(key) else {
+                        continue;
+                    };
+                    let row = ui.horizontal(|ui| {
+                        if state.manual_var_order {
+                            var_drag_handle(ui, i);
+                        }
+                        name_label(ui, key);
+                        rule_menu(ui, &mut state.var_rules, key);
+                    });
+                    ui.vertical(|ui| {
+                        if ui
+                            .add(egui::TextEdit::multiline(value).desired_rows(1).desired_width(160.0))
+                            .changed()
+                        {
+                            state.log.edit("Edited the constants");
+                        }
+                        rule_error(ui, state.var_rules.get(key), value);
+                    });
+                    if ui.small_button("Remove").clicked() {
+                        to_delete.push(key.to_owned());
+                    }
+                    if let Some(from) = row.response.dnd_release_payload::<usize>() {
+                        moved = Some((*from, i));
+                    }
+                    ui.end_row();
+                }
+            });
+    } else {
+        for (i, key) in order.iter().enumerate() {
+            let Some(value) = state.vars.get_mut(key) else {
+                continue;
+            };
+            let row = ui.horizontal(|ui| {
+                if state.manual_var_order {
+                    var_drag_handle(ui, i);
+                }
+                name_label(ui, key);
+                rule_menu(ui, &mut state.var_rules, key);
+                if ui.add(egui::TextEdit::multiline(value).desired_rows(1)).changed() {
+                    state.log.edit("Edited the constants");
+                }
+                if ui.button("Remove").clicked() {
+                    to_delete.push(key.to_owned());
+                }
+                rule_error(ui, state.var_rules.get(key), value);
+            });
+            if let Some(from) = row.response.dnd_release_payload::<usize>() {
+                moved = Some((*from, i));
+            }
+        }
+    }
+    if let Some((from, to)) = moved
+        && from < state.var_order.len()
+        && to < state.var_order.len()
+    {
+        let name = state.var_order.remove(from);
+        state.var_order.insert(to, name);
+    }
+    for var in to_delete {
+        state.vars.remove(&var);
+        state.var_rules.remove(&var);
+        state.log.push(action_log::Kind::Edit, format!("Removed the constant `{var}`"));
+    }
+    // add vars, compact pages use `add_var_button` instead
+    if is_compact(ui.ctx()) {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut state.new_var.0)
+                .id(Section::Constants.focus_id())
+                .hint_text("Name"),
+        );
+        ui.add(egui::TextEdit::multiline(&mut state.new_var.1).desired_rows(1).hint_text("Value"));
+        if ui.button("Add").clicked() {
+            add_var(state);
+        }
+    });
+}
+
+/// Picks the validation rule of the constant `name`.
+fn rule_menu(ui: &mut egui::Ui, rules: &mut BTreeMap<String, Rule>, name: &str) {
+    let label = rules.get(name).map_or("No rule", Rule::name);
+    ui.menu_button(label, |ui| {
+        if ui.radio(!rules.contains_key(name), "No rule").clicked() {
+            rules.remove(name);
+        }
+        for rule in Rule::builtin() {
+            let selected = rules.get(name).is_some_and(|current| current.same_kind(&rule));
+            if ui.radio(selected, rule.name()).clicked() && !selected {
+                rules.insert(name.to_owned(), rule);
+            }
+        }
+        if let Some(Rule::Regex(pattern)) = rules.get_mut(name) {
+            ui.add(egui::TextEdit::singleline(pattern).hint_text("Pattern, e.g. ^[a-z]+$"));
+        }
+    })
+    .response
+    .on_hover_text("Checks the value while editing and before running");
+}
+
+/// Flags `value` if it breaks `rule`.
+fn rule_error(ui: &mut egui::Ui, rule: Option<&Rule>, value: &str) {
+    let Some(rule) = rule else {
+        return;
+    };
+    if let Err(e) = ui.memory_mut(|mem| mem.caches.cache::<RuleCheckCache>().get((rule, value))) {
+        ui.colored_label(ui.visuals().error_fg_color, format!("✖ {e}"));
+    }
+}
+
+/// Results of [`Rule::check`], so a value is only checked again, and a pattern
+/// compiled again, after the value or the rule changed.
+type RuleCheckCache = egui::cache::FrameCache<Result<(), String>, RuleCheck>;
+
+#[derive(Default)]
+struct RuleCheck;
+
+impl egui::cache::ComputerMut<(&Rule, &str), Result<(), String>> for RuleCheck {
+    fn compute(&mut self, (rule, value): (&Rule, &str)) -> Result<(), String> {
+        rule.check(value)
+    }
+}
+
+/// Adds the constant typed into `new_var`, filling in the default name and value.
+fn add_var(state: &mut App) {
+    let mut key: String = state.new_var.0.drain(..).collect();
+    if key.is_empty() && !state.var_name_prefix.is_empty() {
+        key = free_name(&state.vars, &state.var_name_prefix);
+    }
+    let mut value: String = state.new_var.1.drain(..).collect();
+    if value.is_empty() {
+        value.clone_from(&state.default_var_value);
+    }
+    add_entry(state, EntryKind::Constant, key, value);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    Constant,
+    Array,
+}
+
+impl EntryKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Constant => "constant",
+            Self::Array => "array",
+        }
+    }
+}
+
+/// A constant or array added under a name that is already taken.
+struct Collision {
+    kind: EntryKind,
+    name: String,
+    value: String,
+}
+
+fn entries(state: &mut App, kind: EntryKind) -> &mut BTreeMap<String, String> {
+    match kind {
+        EntryKind::Constant => &mut state.vars,
+        EntryKind::Array => &mut state.arrays,
+    }
+}
+
+/// Adds a constant or array, asking what to do instead if `name` is taken.
+fn add_entry(state: &mut App, kind: EntryKind, name: String, value: String) {
+    match entries(state, kind).entry(name) {
+        std::collections::btree_map::Entry::Vacant(entry) => {
+            let message = format!("Added the {} `{}`", kind.name(), entry.key());
+            entry.insert(value);
+            state.log.push(action_log::Kind::Edit, message);
+        }
+        std::collections::btree_map::Entry::Occupied(entry) => {
+            let name = entry.key().clone();
+            state.collision = Some(Collision { kind, name, value });
+        }
+    }
+}
+
+/// Asks whether to overwrite or rename an entry added under a taken name.
+/// Cancelling puts the name and value back into the add row.
+fn collision_modal(state: &mut App, ctx: &egui::Context) {
+    let Some(collision) = &state.collision else {
+        return;
+    };
+    let what = collision.kind.name();
+    let taken = match collision.kind {
+        EntryKind::Constant => &state.vars,
+        EntryKind::Array => &state.arrays,
+    };
+    let renamed = new_entry_name(taken, &collision.name, "");
+    let mut choice = None;
+    let modal = egui::Modal::new(egui::Id::new("add_collision")).show(ctx, |ui| {
+        ui.label(format!("A {what} named `{}` already exists.", collision.name));
+        ui.horizontal(|ui| {
+            if ui.button("Overwrite").clicked() {
+                choice = Some(collision.name.clone());
+            }
+            if ui.button(format!("Rename to `{renamed}`")).clicked() {
+                choice = Some(renamed.clone());
+            }
+            if ui.button("Cancel").clicked() {
+                ui.close();
+            }
+        });
+    });
+    if let Some(name) = choice {
+        if let Some(Collision { kind, value, .. }) = state.collision.take() {
+            state.log.push(action_log::Kind::Edit, format!("Set the {} `{name}`", kind.name()));
+            entries(state, kind).insert(name, value);
+        }
+    } else if modal.should_close()
+        && let Some(Collision { kind, name, value }) = state.collision.take()
+    {
+        let row = match kind {
+            EntryKind::Constant => &mut state.new_var,
+            EntryKind::Array => &mut state.new_array,
+        };
+        *row = (name, value);
+    }
+}
+
+fn is_compact(ctx: &egui::Context) -> bool {
+    ctx.content_rect().width() < COMPACT_WIDTH
+}
+
+/// Floating button opening [`add_var_modal`] on compact pages.
+fn add_var_button(state: &mut App, ctx: &egui::Context) {
+    if !is_compact(ctx) || state.locked {
+        return;
+    }
+    egui::Area::new(egui::Id::new("add_var_button"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-16.0, -16.0))
+        .show(ctx, |ui| {
+            let button = egui::Button::new(egui::RichText::new("+ Constant").heading())
+                .min_size(egui::vec2(56.0, 56.0));
+            if ui.add(button).clicked() {
+                state.adding_var = true;
+            }
+        });
+}
+
+/// Adds a constant with large fields and buttons, for touch screens.
+fn add_var_modal(state: &mut App, ctx: &egui::Context) {
+    if !state.adding_var {
+        return;
+    }
+    let mut added = false;
+    let modal = egui::Modal::new(egui::Id::new("add_var")).show(ctx, |ui| {
+        ui.heading("New constant");
+        let field_width = ui.available_width().min(COMPACT_WIDTH);
+        ui.add(
+            egui::TextEdit::singleline(&mut state.new_var.0)
+                .font(egui::TextStyle::Heading)
+                .desired_width(field_width)
+                .hint_text("Name"),
+        );
+        ui.add(
+            egui::TextEdit::multiline(&mut state.new_var.1)
+                .font(egui::TextStyle::Heading)
+                .desired_width(field_width)
+                .desired_rows(3)
+                .hint_text("Value"),
+        );
+        ui.horizontal(|ui| {
+            let size = egui::vec2(96.0, 44.0);
+            added = ui.add(egui::Button::new(egui::RichText::new("Add").heading()).min_size(size)).clicked();
+            if ui.add(egui::Button::new(egui::RichText::new("Cancel").heading()).min_size(size)).clicked() {
+                ui.close();
+            }
+        });
+    });
+    if added {
+        add_var(state);
+    }
+    if added || modal.should_close() {
+        state.adding_var = false;
+    }
+}
+
+/// Drops removed constants from `var_order` and appends the ones added since.
+fn sync_var_order(state: &mut App) {
+    state.var_order.retain(|name| state.vars.contains_key(name));
+    for name in state.vars.keys() {
+        if !state.var_order.contains(name) {
+            state.var_order.push(name.clone());
+        }
+    }
+}
+
+/// Handle dragging the constant at `index` of `var_order`.
+fn var_drag_handle(ui: &mut egui::Ui, index: usize) {
+    let id = egui::Id::new(("var_drag", index));
+    ui.dnd_drag_source(id, index, |ui| {
+        ui.label("☰");
+    })
+    .response
+    .on_hover_cursor(egui::CursorIcon::Grab);
+}
+
+/// Name of a constant or array, flagged if a loop variable shadows it.
+fn name_label(ui: &mut egui::Ui, name: &str) {
+    ui.label(name);
+    if LOOP_VARIABLES.contains(&name) {
+        ui.colored_label(ui.visuals().warn_fg_color, "shadowed in loops")
+            .on_hover_text(LOOP_VARIABLES_HELP);
+    }
+}
+
+/// Item count and the first couple of items, e.g. `3 items: first, second, …`.
+fn array_preview(value: &[&str]) -> String {
+    const ITEMS: usize = 2;
+    const ITEM_CHARS: usize = 16;
+    let count = value.len();
+    let mut items: Vec<String> = value
+        .iter()
+        .copied()
+        .take(ITEMS)
+        .map(|item| {
+            if item.chars().count() > ITEM_CHARS {
+                item.chars().take(ITEM_CHARS).chain(['…']).collect()
+            } else {
+                item.to_owned()
+            }
+        })
+        .collect();
+    if count > ITEMS {
+        items.push("…".into());
+    }
+    match count {
+        0 => "empty".into(),
+        1 => format!("1 item: {}", items.join(", ")),
+        _ => format!("{count} items: {}", items.join(", ")),
+    }
+}
+
+/// Overrides what the blank last line of the array `name` becomes.
+fn trailing_line_menu(
+    ui: &mut egui::Ui,
+    overrides: &mut BTreeMap<String, TrailingLine>,
+    name: &str,
+    default: TrailingLine,
+) {
+    let mut selected = overrides.get(name).copied();
+    egui::ComboBox::from_id_salt(("trailing_line", name))
+        .selected_text(match selected {
+            Some(trailing) => format!("Blank last line: {}", trailing.label()),
+            None => "Blank last line: default".into(),
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, format!("Default ({})", default.label()));
+            for trailing in TrailingLine::ALL {
+                ui.selectable_value(&mut selected, Some(trailing), trailing.label());
+            }
+        });
+    match selected {
+        Some(trailing) => overrides.insert(name.to_owned(), trailing),
+        None => overrides.remove(name),
+    };
+}
+
+/// Returns `name` if it is free in `map`, otherwise a numbered variant of it.
+/// A blank `name` is replaced with `fallback`.
+fn new_entry_name<V>(map: &BTreeMap<String, V>, name: &str, fallback: &str) -> String {
+    let name = if name.is_empty() { fallback } else { name };
+    if map.contains_key(name) {
+        free_name(map, &format!("{name}_"))
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Returns the first of `<prefix>1`, `<prefix>2`, ... that is not a key of `map`.
+fn free_name<V>(map: &BTreeMap<String, V>, prefix: &str) -> String {
+    (1..)
+        .map(|n: usize| format!("{prefix}{n}"))
+        .find(|name| !map.contains_key(name))
+        .unwrap_or_default()
+}
+
+fn arrays_editor(state: &mut App, ui: &mut egui::Ui) {
+    let mut to_delete = Vec::with_capacity(state.arrays.len());
+    for (key, value) in state.arrays.iter_mut() {
+        ui.horizontal(|ui| {
+            name_label(ui, key);
+            let trailing = state.array_trailing_lines.get(key).copied();
+            let items = inputs::array_items(value, trailing.unwrap_or(state.trailing_line));
+            ui.weak(array_preview(&items));
+            // display-only, mirrors `_index_` of the items
+            if state.show_item_numbers {
+                let numbers: Vec<String> = (0..items.len())
+                    .map(|i| (i + state.item_numbers_base).to_string())
+                    .collect();
+                ui.label(egui::RichText::new(numbers.join("\n")).weak());
+            }
+            if ui.add(egui::TextEdit::multiline(value).desired_rows(1)).changed() {
+                state.log.edit("Edited the arrays");
+            }
+            trailing_line_menu(ui, &mut state.array_trailing_lines, key, state.trailing_line);
+            if ui.button("Remove").clicked() {
+                to_delete.push(key.to_owned());
+            }
+        });
+    }
+    for var in to_delete {
+        state.arrays.remove(&var);
+        state.array_trailing_lines.remove(&var);
+        state.log.push(action_log::Kind::Edit, format!("Removed the array `{var}`"));
+    }
+    // add vars
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut state.new_array.0)
+                .id(Section::Arrays.focus_id())
+                .hint_text("Name"),
+        );
+        ui.add(egui::TextEdit::multiline(&mut state.new_array.1).desired_rows(1).hint_text("Values"));
+        if ui.button("Add").clicked() {
+            let name = state.new_array.0.drain(..).collect();
+            let items = state.new_array.1.drain(..).collect();
+            add_entry(state, EntryKind::Array, name, items);
+        }
+    });
+}
+
+fn transfer_editor(state: &mut App, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        if ui.button("Export").clicked() {
+            let inputs = Inputs {
+                vars: Some(state.vars.clone()),
+                arrays: Some(run_arrays(state)),
+                code: Some(state.code.clone()),
+                notes: (!state.notes.is_empty()).then(|| state.notes.clone()),
+                run_config: Some(RunConfig {
+                    trailing_line: state.trailing_line,
+                    array_trailing_lines: state.array_trailing_lines.clone(),
+                    keep_string_tabs: state.keep_string_tabs,
+                }),
+            };
+            state.transfer = inputs.to_json();
+            state.transfer_errors.clear();
+            state.unsaved_runs = 0;
+        }
+        let import = ui
+            .add_enabled(!state.locked, egui::Button::new("Import"))
+            .on_disabled_hover_text("Unlock to replace the code, constants and arrays");
+        if import.clicked() {
+            let result = if state.strict_import {
+                Inputs::from_json_strict(&state.transfer)
+            } else {
+                Inputs::from_json(&state.transfer)
+            };
+            match result {
+                Ok(inputs) => {
+                    import_inputs(state, inputs);
+                    state.transfer_errors.clear();
+                    state.log.push(action_log::Kind::Import, "Imported JSON");
+                }
+                Err(errors) => {
+                    state.log.push(action_log::Kind::Error, format!("JSON import failed: {}", errors.join("; ")));
+                    state.transfer_errors = errors;
+                }
+            }
+        }
+        ui.checkbox(&mut state.strict_import, "Validate schema")
+            .on_hover_text("Reject unexpected keys and values of the wrong type");
+    });
+    for error in &state.transfer_errors {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+    ui.add(
+        egui::TextEdit::multiline(&mut state.transfer)
+            .code_editor()
+            .desired_width(f32::INFINITY)
+            .desired_rows(4)
+            .hint_text(r#"{ "vars": {}, "arrays": {}, "code": "" }"#),
+    );
+}
+
+/// Replaces what the imported inputs contain, keeping the rest.
+fn import_inputs(state: &mut App, inputs: Inputs) {
+    if let Some(config) = inputs.run_config {
+        state.trailing_line = config.trailing_line;
+        state.array_trailing_lines = config.array_trailing_lines;
+        state.keep_string_tabs = config.keep_string_tabs;
+    }
+    if let Some(vars) = inputs.vars {
+        state.vars = vars;
+    }
+    if let Some(arrays) = inputs.arrays {
+        let arrays = arrays
+            .into_iter()
+            .map(|(name, items)| {
+                let text = inputs::array_text(&items, trailing_line(state, &name));
+                (name, text)
+            })
+            .collect();
+        state.arrays = arrays;
+        let arrays = &state.arrays;
+        state.array_trailing_lines.retain(|name, _| arrays.contains_key(name));
+    }
+    if let Some(code) = inputs.code {
+        state.code = code;
+    }
+    if let Some(notes) = inputs.notes {
+        state.notes = notes;
+    }
+}
+
+fn env_import(state: &mut App, ui: &mut egui::Ui) {
+    ui.label("Adds the `KEY=VALUE` lines of a .env file to the constants, or drop the file onto the window.");
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.env_path).hint_text("Path to .env"));
+        if ui.add_enabled(!state.env_path.is_empty(), egui::Button::new("Import")).clicked() {
+            let path = state.env_path.clone();
+            let src = std::fs::read_to_string(&path).map_err(|e| e.to_string());
+            import_env(state, src, &path);
+        }
+    });
+    for error in &state.env_errors {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+}
+
+/// Adds the constants of a read `.env` file, reporting its malformed lines.
+fn import_env(state: &mut App, src: Result<String, String>, name: &str) {
+    let src = match src {
+        Ok(src) => src,
+        Err(e) => {
+            state.env_errors = vec![format!("Could not read {name}: {e}")];
+            state.log.push(action_log::Kind::Error, format!("Could not read {name}: {e}"));
+            return;
+        }
+    };
+    let (vars, errors) = dotenv::parse(&src);
+    let replaced: Vec<&str> = vars
+        .iter()
+        .filter(|(key, value)| state.vars.get(*key).is_some_and(|old| old != *value))
+        .map(|(key, _)| key.as_str())
+        .collect();
+    state.notice = format!(
+        "Imported {} {} from {name}",
+        vars.len(),
+        if vars.len() == 1 { "constant" } else { "constants" }
+    );
+    if !replaced.is_empty() {
+        state.notice += &format!(", replaced the value of `{}`", replaced.join("`, `"));
+    }
+    state.log.push(action_log::Kind::Import, state.notice.clone());
+    state.vars.extend(vars);
+    state.env_errors = errors;
+}
+
+/// Tokenizer statistics collected by [`check`].
+struct ParseStats {
+    tokenize_time: Duration,
+    tokens: usize,
+}
+
+impl ParseStats {
+    fn is_slow(&self) -> bool {
+        self.tokenize_time >= SLOW_TOKENIZE || self.tokens >= MANY_TOKENS
+    }
+}
+
+/// Parses and compiles `code` without running it, returns the rendered error if there is one.
+fn check(code: &str, keep_string_tabs: bool) -> (Option<String>, ParseStats) {
+    let (code, _) = expand_tabs(code, keep_string_tabs);
+    let mut message = Vec::new();
+    let start = Instant::now();
+    let tokens = ast(&code);
+    let mut stats = ParseStats {
+        tokenize_time: start.elapsed(),
+        tokens: 0,
+    };
+    let written = match tokens {
+        Ok(tokens) => {
+            stats.tokens = tokens.len();
+            match gen_ir(&code, tokens) {
+                Ok(_) => return (None, stats),
+                Err(e) => e.write_message(&mut message, "index.pipa", &code),
+            }
+        }
+        Err(e) => e.write_message(&mut message, "index.pipa", &code),
+    };
+    let diagnostics = match written {
+        Ok(()) => String::from_utf8_lossy(&message).into_owned(),
+        Err(e) => format!("The program does not compile, and rendering the error failed: {e}"),
+    };
+    (Some(diagnostics), stats)
+}
+
+fn apply_check(state: &mut App) {
+    // only the macro warnings point into the code, the others are about the last run
+    state.warnings.retain(|warning| warning.offset.is_none());
+    state.warnings.extend(macro_warnings(&state.code));
+    let (diagnostics, stats) = check(&state.code, state.keep_string_tabs);
+    if diagnostics.is_none() {
+        state.macros = analysis::macros(&state.code);
+    }
+    state.diagnostics = diagnostics.unwrap_or_default();
+    state.parse_stats = Some(stats);
+}
+
+/// Macros applied before they are defined, checked before running.
+fn macro_warnings(code: &str) -> impl Iterator<Item = Warning> {
+    analysis::undefined_macros(code).into_iter().map(|(name, offset)| Warning {
+        message: format!("Macro `{name}` is not defined before it is applied"),
+        offset: Some(offset),
+    })
+}
+
+/// Everything a run depends on.
+#[derive(Clone)]
+struct RunInput {
+    code: String,
+    vars: BTreeMap<String, String>,
+    /// Items of the arrays as the vm gets them, see [`run_arrays`].
+    arrays: BTreeMap<String, Vec<String>>,
+}
+
+/// A problem found around a run that does not stop it.
+struct Warning {
+    message: String,
+    /// Byte offset in the code the warning is about.
+    offset: Option<usize>,
+}
+
+impl From<String> for Warning {
+    fn from(message: String) -> Self {
+        Self { message, offset: None }
+    }
+}
+
+/// Vm state and IR of a compiled program.
+struct Dumps {
+    console: String,
+    ir: String,
+}
+
+/// What a run produced.
+struct RunOutput {
+    output: String,
+    /// Rendered compile error, empty if the program compiled.
+    diagnostics: String,
+    /// `None` if the program did not compile.
+    dumps: Option<Dumps>,
+    runtime_error: Option<String>,
+}
+
+impl RunOutput {
+    /// The rendered compile error is only shown as diagnostics, there is no output.
+    fn compile_error(message: Vec<u8>) -> Self {
+        Self {
+            output: String::new(),
+            diagnostics: String::from_utf8(message).unwrap(),
+            dumps: None,
+            runtime_error: None,
+        }
+    }
+
+    fn succeeded(&self) -> bool {
+        self.dumps.is_some() && self.runtime_error.is_none()
+    }
+}
+
+/// One-line outcome of the last run, shown above the output.
+enum RunSummary {
+    Ran { time: Duration, lines: usize },
+    Failed(String),
+}
+
+impl RunSummary {
+    fn new(result: &RunOutput, time: Duration) -> Self {
+        if let Some(first) = result.diagnostics.lines().find(|line| !line.trim().is_empty()) {
+            let line = diagnostic_line(&result.diagnostics)
+                .map(|line| format!(" on line {line}"))
+                .unwrap_or_default();
+            Self::Failed(format!("compile error{line}: {}", first.trim()))
+        } else if let Some(error) = &result.runtime_error {
+            Self::Failed(format!("runtime error: {error}"))
+        } else {
+            Self::Ran {
+                time,
+                lines: result.output.lines().count(),
+            }
+        }
+    }
+}
+
+/// Line a rendered diagnostic points at, taken from its `index.pipa:LINE:COLUMN` location.
+fn diagnostic_line(diagnostics: &str) -> Option<usize> {
+    let (_, location) = diagnostics.split_once("index.pipa:")?;
+    let digits: String = location.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+fn execute(input: &RunInput) -> RunOutput {
+    let mut output = Vec::new();
+    // tokenize + lex
+    let tokens = match ast(&input.code) {
+        Ok(r) => r,
+        Err(e) => {
+            e.write_message(&mut output, "index.pipa", &input.code).unwrap();
+            return RunOutput::compile_error(output);
+        }
+    };
+
+    // ir
+    let ir = match gen_ir(&input.code, tokens) {
+        Ok(ir) => ir,
+        Err(e) => {
+            e.write_message(&mut output, "index.pipa", &input.code).unwrap();
+            return RunOutput::compile_error(output);
+        }
+    };
+    // convert vars
+    let mut vars = BTreeMap::new();
+    let mut arrays = BTreeMap::new();
+
+    for (key, value) in input.vars.clone() {
+        vars.insert(key.into(), value.into());
+    }
+
+    for (key, value) in input.arrays.clone() {
+        arrays.insert(key.into(), value.into_iter().map(|s| s.into()).collect());
+    }
+
+    // run
+    let mut vm = Vm::new(vars, arrays);
+
+    let runtime_error = vm.run(&mut output, &ir).err().map(|e| format!("{e:?}"));
+
+    // fill console
+    let mut console = Vec::new();
+    vm.dump_state(&mut console).unwrap();
+    write!(&mut console, "\n").unwrap();
+    let mut ir_dump = Vec::new();
+    dump_ir(&mut ir_dump, &ir).unwrap();
+    console.extend_from_slice(&ir_dump);
+
+    RunOutput {
+        output: String::from_utf8(output).unwrap(),
+        diagnostics: String::new(),
+        dumps: Some(Dumps {
+            console: String::from_utf8(console).unwrap(),
+            ir: String::from_utf8_lossy(&ir_dump).into_owned(),
+        }),
+        runtime_error,
+    }
+}
+
+/// How the array `name` treats its blank last line.
+fn trailing_line(state: &App, name: &str) -> TrailingLine {
+    state.array_trailing_lines.get(name).copied().unwrap_or(state.trailing_line)
+}
+
+/// Items of every array, with the blank last lines treated as set for each.
+fn run_arrays(state: &App) -> BTreeMap<String, Vec<String>> {
+    state
+        .arrays
+        .iter()
+        .map(|(name, value)| {
+            let items = inputs::array_items(value, trailing_line(state, name));
+            (name.clone(), items.into_iter().map(String::from).collect())
+        })
+        .collect()
+}
+
+/// Replaces every tab with four spaces, optionally except those in string literals.
+/// Returns the byte ranges of the inserted spaces.
+fn expand_tabs(code: &str, keep_string_tabs: bool) -> (String, Vec<std::ops::Range<usize>>) {
+    let kept = if keep_string_tabs {
+        analysis::string_literals(code)
+    } else {
+        Vec::new()
+    };
+    let mut kept = kept.iter().peekable();
+    let mut expanded = String::with_capacity(code.len());
+    let mut ranges = Vec::new();
+    for (i, c) in code.char_indices() {
+        if c != '\t' {
+            expanded.push(c);
+            continue;
+        }
+        // the literals are sorted, so they are walked once
+        while kept.next_if(|range| range.end <= i).is_some() {}
+        if kept.peek().is_some_and(|range| range.contains(&i)) {
+            expanded.push(c);
+        } else {
+            let start = expanded.len();
+            expanded.push_str("    ");
+            ranges.push(start..expanded.len());
+        }
+    }
+    (expanded, ranges)
+}
+
+fn run_vm(state: &mut App) {
+    let (code, transformed) = expand_tabs(&state.code, state.keep_string_tabs);
+    // a locked editor is read-only, the expanded copy is only run
+    if state.locked {
+        state.transformed.clear();
+    } else {
+        state.code.clone_from(&code);
+        state.transformed = transformed;
+    }
+    let input = RunInput {
+        code,
+        vars: state.vars.clone(),
+        arrays: run_arrays(state),
+    };
+    let start = Instant::now();
+    let result = execute(&input);
+    let time = start.elapsed();
+
+    state.warnings.clear();
+    state.notice.clear();
+    for name in state.vars.keys().chain(state.arrays.keys()) {
+        if LOOP_VARIABLES.contains(&name.as_str()) {
+            state
+                .warnings
+                .push(format!("`{name}` is shadowed by the loop variable of the same name").into());
+        }
+    }
+    for (name, rule) in &state.var_rules {
+        if let Some(Err(e)) = state.vars.get(name).map(|value| rule.check(value)) {
+            state.warnings.push(format!("Constant `{name}` {e}").into());
+        }
+    }
+    state.warnings.extend(macro_warnings(&state.code));
+    if result.dumps.is_some() && state.warn_empty_arrays {
+        for name in analysis::referenced_arrays(&state.code) {
+            if input.arrays.get(&name).is_some_and(Vec::is_empty) {
+                state
+                    .warnings
+                    .push(format!("Array `{name}` is empty, loops over it produced no output").into());
+            }
+        }
+    }
+    if result.dumps.is_some() {
+        state.macros = analysis::macros(&state.code);
+    }
+    if result.succeeded() {
+        state.last_success = Some(input);
+    }
+    state.unsaved_runs += 1;
+    apply_run(state, result, time);
+}
+
+/// Runs the last program that ran without errors, leaving the editor and inputs as they are.
+fn rerun_last_success(state: &mut App) {
+    let Some(input) = &state.last_success else {
+        return;
+    };
+    let start = Instant::now();
+    let result = execute(input);
+    let time = start.elapsed();
+    state.warnings.clear();
+    state.notice = "Showing the output of the last successful run, the editor is unchanged".into();
+    apply_run(state, result, time);
+}
+
+fn apply_run(state: &mut App, result: RunOutput, time: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if result.succeeded() {
+        state.output_file.write(&result.output);
+    }
+    state.stats.record(time, &result.output);
+    state.summary = Some(RunSummary::new(&result, time));
+    if let Some(error) = result.diagnostics.lines().find(|line| !line.trim().is_empty()) {
+        state.log.push(action_log::Kind::Error, format!("Run failed: {error}"));
+    } else if let Some(error) = &result.runtime_error {
+        state.log.push(action_log::Kind::Error, format!("Run failed: {error}"));
+    } else {
+        state.log.push(
+            action_log::Kind::Run,
+            format!("Ran in {:.2} ms, {} bytes of output", time.as_secs_f64() * 1000.0, result.output.len()),
+        );
+    }
+    if state.auto_console {
+        state.console_open = Some(!result.succeeded());
+    }
+    state.output = result.output;
+    output_changed(state);
+    state.diagnostics = result.diagnostics;
+    if let Some(dumps) = result.dumps {
+        state.console = dumps.console;
+        state.ir_changes = state.ir.as_ref().map(|previous| {
+            diff::lines(previous, &dumps.ir)
+                .into_iter()
+                .map(|(change, line)| (change, line.to_owned()))
+                .collect()
+        });
+        state.ir = Some(dumps.ir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_tabs_returns_the_expanded_ranges() {
+        let (code, ranges) = expand_tabs("\ta\t\"\t\"", false);
+        assert_eq!(code, "    a    \"    \"");
+        assert_eq!(ranges, [0..4, 5..9, 10..14]);
+    }
+
+    #[test]
+    fn expand_tabs_keeps_tabs_in_string_literals() {
+        let (code, ranges) = expand_tabs("\t{{\"a\tb\" \"\t\"\t}}", true);
+        assert_eq!(code, "    {{\"a\tb\" \"\t\"    }}");
+        assert_eq!(ranges, [0..4, 15..19]);
+    }
 }
@@ -0,0 +1,68 @@
+//! Copying HTML output to the clipboard as HTML, on the web only.
+//!
+//! Pages cannot put files on the clipboard: a `ClipboardItem` only holds
+//! representations of the copied data, such as `text/html`. Pasting HTML
+//! output into a rich text editor then keeps its markup rendered.
+//!
+//! Uses `navigator.clipboard.write` with a `ClipboardItem`, which older browsers lack.
+
+use std::sync::OnceLock;
+
+use eframe::wasm_bindgen::JsValue;
+
+/// Whether the browser can put `text/html` on the clipboard, checked once.
+/// `ClipboardItem.supports` is newer than `ClipboardItem` itself and calling it
+/// where it is missing throws, so its presence is checked first.
+pub fn html_copy_supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        let item = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("ClipboardItem"));
+        item.is_ok_and(|item| {
+            !item.is_undefined()
+                && js_sys::Reflect::has(&item, &JsValue::from_str("supports")).unwrap_or(false)
+                && web_sys::ClipboardItem::supports("text/html")
+        })
+    })
+}
+
+/// Copies `html` as `text/html` with a `text/plain` copy for plain text targets.
+/// Falls back to copying plain text if the write is rejected.
+pub fn copy_html(html: &str) {
+    let Some(clipboard) = web_sys::window().map(|window| window.navigator().clipboard()) else {
+        return;
+    };
+    let written = (|| {
+        let record = js_sys::Object::new();
+        for mime in ["text/html", "text/plain"] {
+            let blob = blob(html, mime)?;
+            js_sys::Reflect::set(
+                &record,
+                &JsValue::from_str(mime),
+                &js_sys::Promise::resolve(&blob),
+            )?;
+        }
+        let item = web_sys::ClipboardItem::new_with_record_from_str_to_blob_promise(&record)?;
+        Ok::<_, JsValue>(clipboard.write(&js_sys::Array::of1(&item)))
+    })();
+    let text = html.to_owned();
+    wasm_bindgen_futures::spawn_local(async move {
+        let written = match written {
+            Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            log::warn!("Copying as HTML failed, copying as text: {e:?}");
+            let promise = clipboard.write_text(&text);
+            if let Err(e) = wasm_bindgen_futures::JsFuture::from(promise).await {
+                log::warn!("Copying failed: {e:?}");
+            }
+        }
+    });
+}
+
+fn blob(text: &str, mime: &str) -> Result<web_sys::Blob, JsValue> {
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let parts = js_sys::Array::of1(&JsValue::from_str(text));
+    web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)
+}
@@ -0,0 +1,124 @@
+//! Line based diffing of texts.
+
+/// Inputs with more line pairs than this are reported as fully replaced
+/// instead of diffed, to bound memory and time.
+const MAX_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Added,
+    Removed,
+}
+
+/// Diffs `old` against `new` line by line using their longest common subsequence.
+pub fn lines<'a>(old: &'a str, new: &'a str) -> Vec<(Change, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let width = new.len() + 1;
+    if (old.len() + 1).saturating_mul(width) > MAX_CELLS {
+        return old
+            .iter()
+            .map(|line| (Change::Removed, *line))
+            .chain(new.iter().map(|line| (Change::Added, *line)))
+            .collect();
+    }
+
+    // lcs[i * width + j] is the length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for (i, a) in old.iter().enumerate().rev() {
+        for (j, b) in new.iter().enumerate().rev() {
+            let value = if a == b {
+                lcs.get((i + 1) * width + j + 1).map_or(0, |n| n + 1)
+            } else {
+                let down = lcs.get((i + 1) * width + j).copied().unwrap_or(0);
+                let right = lcs.get(i * width + j + 1).copied().unwrap_or(0);
+                down.max(right)
+            };
+            if let Some(cell) = lcs.get_mut(i * width + j) {
+                *cell = value;
+            }
+        }
+    }
+
+    let at = |i: usize, j: usize| lcs.get(i * width + j).copied().unwrap_or(0);
+    let mut changes = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while let (Some(a), Some(b)) = (old.get(i), new.get(j)) {
+        if a == b {
+            changes.push((Change::Same, *a));
+            i += 1;
+            j += 1;
+        } else if at(i + 1, j) >= at(i, j + 1) {
+            changes.push((Change::Removed, *a));
+            i += 1;
+        } else {
+            changes.push((Change::Added, *b));
+            j += 1;
+        }
+    }
+    changes.extend(old.iter().skip(i).map(|line| (Change::Removed, *line)));
+    changes.extend(new.iter().skip(j).map(|line| (Change::Added, *line)));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Change::{Added, Removed, Same};
+
+    #[test]
+    fn identical_texts_are_unchanged() {
+        assert_eq!(lines("a\nb", "a\nb"), [(Same, "a"), (Same, "b")]);
+        assert!(lines("", "").is_empty());
+    }
+
+    #[test]
+    fn pure_insert_and_delete() {
+        assert_eq!(lines("", "a\nb"), [(Added, "a"), (Added, "b")]);
+        assert_eq!(lines("a\nb", ""), [(Removed, "a"), (Removed, "b")]);
+        assert_eq!(
+            lines("a\nc", "a\nb\nc"),
+            [(Same, "a"), (Added, "b"), (Same, "c")]
+        );
+        assert_eq!(
+            lines("a\nb\nc", "a\nc"),
+            [(Same, "a"), (Removed, "b"), (Same, "c")]
+        );
+    }
+
+    #[test]
+    fn interleaved_changes() {
+        assert_eq!(
+            lines("a\nb\nc\nd", "a\nx\nc\ny\nd\ne"),
+            [
+                (Same, "a"),
+                (Removed, "b"),
+                (Added, "x"),
+                (Same, "c"),
+                (Added, "y"),
+                (Same, "d"),
+                (Added, "e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn large_inputs_are_fully_replaced() {
+        let text = "same\n".repeat(2000);
+        let changes = lines(&text, &text);
+        assert_eq!(changes.len(), 4000);
+        assert!(
+            changes
+                .iter()
+                .take(2000)
+                .all(|change| *change == (Removed, "same"))
+        );
+        assert!(
+            changes
+                .iter()
+                .skip(2000)
+                .all(|change| *change == (Added, "same"))
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Parsing of `.env` files.
+//!
+//! Every non-blank line is `KEY=VALUE`, optionally prefixed with `export`.
+//! Lines starting with `#` are comments. Values may be:
+//!
+//! * unquoted - trimmed, a ` #` starts a comment,
+//! * single-quoted - taken literally,
+//! * double-quoted - with `\n`, `\t`, `\"` and `\\` escapes, may span several lines.
+//!
+//! ```text
+//! # database
+//! export HOST=localhost  # local only
+//! GREETING="Hello,\nworld"
+//! PATTERN='$(name)'
+//! ```
+
+use std::collections::BTreeMap;
+
+/// Parses `src`, returning the valid entries and a message for every malformed line.
+/// Later entries override earlier ones with the same key.
+pub fn parse(src: &str) -> (BTreeMap<String, String>, Vec<String>) {
+    let mut vars = BTreeMap::new();
+    let mut errors = Vec::new();
+    let mut lines = src.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        // only trimmed at the start, trailing spaces may be part of a quoted value
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+        let Some((key, value)) = line.split_once('=') else {
+            errors.push(format!("line {}: expected `KEY=VALUE`", i + 1));
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-')
+        {
+            errors.push(format!("line {}: invalid key `{key}`", i + 1));
+            continue;
+        }
+        let value = value.trim_start();
+        let parsed = if let Some(rest) = value.strip_prefix('"') {
+            // the value may continue on the following lines, which are only
+            // consumed once the closing quote is found
+            let mut ahead = lines.clone();
+            let mut raw = rest.to_owned();
+            loop {
+                if let Some(parsed) = double_quoted(&raw) {
+                    lines = ahead;
+                    break parsed;
+                }
+                let Some((_, next)) = ahead.next() else {
+                    break Err("missing closing `\"`");
+                };
+                raw.push('\n');
+                raw.push_str(next);
+            }
+        } else if let Some(rest) = value.strip_prefix('\'') {
+            single_quoted(rest)
+        } else {
+            let end = value.find(" #").unwrap_or(value.len());
+            Ok(value.get(..end).unwrap_or_default().trim_end().to_owned())
+        };
+        match parsed {
+            Ok(value) => {
+                vars.insert(key.to_owned(), value);
+            }
+            Err(e) => errors.push(format!("line {}: {e}", i + 1)),
+        }
+    }
+    (vars, errors)
+}
+
+/// Unescapes a double-quoted value that starts after the opening quote.
+/// Returns `None` if the closing quote is not in `raw`.
+fn double_quoted(raw: &str) -> Option<Result<String, &'static str>> {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some(trailing(raw.get(i + 1..).unwrap_or_default()).map(|()| value)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, c @ ('"' | '\\'))) => value.push(c),
+                Some((_, c)) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => return None,
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Takes a single-quoted value literally, `raw` starts after the opening quote.
+fn single_quoted(raw: &str) -> Result<String, &'static str> {
+    let (value, rest) = raw.split_once('\'').ok_or("missing closing `'`")?;
+    trailing(rest)?;
+    Ok(value.to_owned())
+}
+
+/// Checks that only whitespace or a comment follows a closing quote.
+fn trailing(rest: &str) -> Result<(), &'static str> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err("unexpected text after the closing quote")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn unquoted_values() {
+        let src = "# database\nexport HOST=localhost  # local only\nPORT = 5432 \nURL=a#b\n\n";
+        let (parsed, errors) = parse(src);
+        assert_eq!(
+            parsed,
+            vars(&[("HOST", "localhost"), ("PORT", "5432"), ("URL", "a#b")])
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn quoted_values() {
+        let src = "A='$(name) \\n' # kept\nB=\"Hello,\\n\\t\\\"world\\\" \\\\ \\x\"\nC=''";
+        let (parsed, errors) = parse(src);
+        assert_eq!(
+            parsed,
+            vars(&[
+                ("A", "$(name) \\n"),
+                ("B", "Hello,\n\t\"world\" \\ \\x"),
+                ("C", "")
+            ])
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn multi_line_double_quoted_value() {
+        let (parsed, errors) = parse("KEY=\"first  \n  second\"\nNEXT=1");
+        assert_eq!(parsed, vars(&[("KEY", "first  \n  second"), ("NEXT", "1")]));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn unterminated_double_quote_keeps_the_following_lines() {
+        let (parsed, errors) = parse("OPEN=\"never closed\nNEXT=1");
+        assert_eq!(parsed, vars(&[("NEXT", "1")]));
+        assert_eq!(errors, ["line 1: missing closing `\"`"]);
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let (parsed, errors) = parse("no equals sign\n=empty\nBAD KEY=1\nQ='open\nT=\"x\" y\nOK=1");
+        assert_eq!(parsed, vars(&[("OK", "1")]));
+        assert_eq!(
+            errors,
+            [
+                "line 1: expected `KEY=VALUE`",
+                "line 2: invalid key ``",
+                "line 3: invalid key `BAD KEY`",
+                "line 4: missing closing `'`",
+                "line 5: unexpected text after the closing quote",
+            ]
+        );
+    }
+
+    #[test]
+    fn later_entries_override_earlier_ones() {
+        let (parsed, _) = parse("A=1\nA=2");
+        assert_eq!(parsed, vars(&[("A", "2")]));
+    }
+}
@@ -0,0 +1,282 @@
+//! Examples loaded into the playground and small snippets of the language
+//! features, inserted at the editor cursor.
+//!
+//! In the code of a snippet `${text}` is a placeholder: `text` is inserted and
+//! selected, and Tab moves on to the next placeholder.
+//!
+//! Examples may ask for values first: `%key%` in their code, constants and
+//! arrays is replaced with what was entered for the [`Param`] with that key.
+
+use std::ops::Range;
+
+use crate::analysis;
+
+pub struct Snippet {
+    pub category: &'static str,
+    pub name: &'static str,
+    /// Code inside a `{{ }}` block.
+    pub code: &'static str,
+}
+
+pub const SNIPPETS: [Snippet; 8] = [
+    Snippet {
+        category: "Strings",
+        name: "Escaped quotes",
+        code: r#""\"${quoted text}\"""#,
+    },
+    Snippet {
+        category: "Strings",
+        name: "Newline and tab",
+        code: r#""${first line}\n\t${indented line}""#,
+    },
+    Snippet {
+        category: "Interpolation",
+        name: "Constant",
+        code: r#""$(${name})""#,
+    },
+    Snippet {
+        category: "Interpolation",
+        name: "Nested interpolation",
+        code: r#""$(${name})" | "<${tag}>$(_)</${tag}>""#,
+    },
+    Snippet {
+        category: "Macros",
+        name: "Macro with index",
+        code: "@${macro} \"$(_index_): $(_item_)\"\n${LIST}[:] | ?${macro}",
+    },
+    Snippet {
+        category: "Macros",
+        name: "Macro with a pipe",
+        code: "@${macro} \"$(_item_)\" | \"<li>$(_)</li>\"\n${LIST}[:] | ?${macro}",
+    },
+    Snippet {
+        category: "Pipes",
+        name: "Multi-stage pipe",
+        code: r#"${LIST}[:] | "$(_)" | "<li>$(_)</li>" | "\n$(_)""#,
+    },
+    Snippet {
+        category: "Arrays",
+        name: "Slice",
+        code: "${LIST}[${1}:${3}]",
+    },
+];
+
+/// Categories of [`SNIPPETS`] in order of first appearance.
+pub fn categories() -> Vec<&'static str> {
+    let mut categories = Vec::new();
+    for snippet in &SNIPPETS {
+        if !categories.contains(&snippet.category) {
+            categories.push(snippet.category);
+        }
+    }
+    categories
+}
+
+/// Removes the placeholder markers from `code`. Returns the code and the byte
+/// ranges of the placeholders in it, followed by an empty range at its end.
+pub fn expand(code: &str) -> (String, Vec<Range<usize>>) {
+    let mut out = String::with_capacity(code.len());
+    let mut stops = Vec::new();
+    let mut rest = code;
+    while let Some(open) = rest.find("${") {
+        let Some(len) = rest.get(open + 2..).and_then(|s| s.find('}')) else {
+            break;
+        };
+        out.push_str(rest.get(..open).unwrap_or_default());
+        let start = out.len();
+        out.push_str(rest.get(open + 2..open + 2 + len).unwrap_or_default());
+        stops.push(start..out.len());
+        rest = rest.get(open + 3 + len..).unwrap_or_default();
+    }
+    out.push_str(rest);
+    stops.push(out.len()..out.len());
+    (out, stops)
+}
+
+/// A value an example asks for before it is loaded.
+pub struct Param {
+    pub key: &'static str,
+    pub prompt: &'static str,
+    /// Used when nothing is entered.
+    pub default: &'static str,
+}
+
+pub struct Example {
+    pub name: &'static str,
+    pub code: &'static str,
+    pub vars: &'static [(&'static str, &'static str)],
+    pub arrays: &'static [(&'static str, &'static str)],
+    pub params: &'static [Param],
+}
+
+pub const EXAMPLES: [Example; 3] = [
+    Example {
+        name: "Greeting",
+        code: "<p>{{ \"%greeting%, $(name)!\" }}</p>",
+        vars: &[("name", "%name%")],
+        arrays: &[],
+        params: &[
+            Param {
+                key: "greeting",
+                prompt: "Enter a greeting",
+                default: "Hello",
+            },
+            Param {
+                key: "name",
+                prompt: "Who to greet",
+                default: "world",
+            },
+        ],
+    },
+    Example {
+        name: "List",
+        code: "<h1>%title%</h1>\n<ul>\n{{\n    @item \"\\n  <li>$(_item_)</li>\"\n    ITEMS[:] | ?item\n}}\n</ul>",
+        vars: &[],
+        arrays: &[("ITEMS", "%first%\n%second%\n%third%")],
+        params: &[
+            Param {
+                key: "title",
+                prompt: "Title of the list",
+                default: "Shopping",
+            },
+            Param {
+                key: "first",
+                prompt: "First item",
+                default: "Apples",
+            },
+            Param {
+                key: "second",
+                prompt: "Second item",
+                default: "Pears",
+            },
+            Param {
+                key: "third",
+                prompt: "Third item",
+                default: "Plums",
+            },
+        ],
+    },
+    Example {
+        name: "Numbered lines",
+        code: "{{\n    @line \"$(_index_). $(_item_)\\n\"\n    LINES[:] | ?line\n}}",
+        vars: &[],
+        arrays: &[("LINES", "first\nsecond\nthird")],
+        params: &[],
+    },
+];
+
+/// Replaces every `%key%` of the `params` in the value of a constant or array
+/// with the value entered for it, or its default if the value is blank or missing.
+pub fn fill(text: &str, params: &[Param], values: &[String]) -> String {
+    substitute(text, params, values, &[])
+}
+
+/// Like [`fill`] for the code of an example, escaping the values that end up in
+/// string literals so quotes and backslashes in them do not end the literal.
+pub fn fill_code(code: &str, params: &[Param], values: &[String]) -> String {
+    substitute(code, params, values, &analysis::string_literals(code))
+}
+
+/// Replaces the placeholders in a single pass, so placeholders in the inserted
+/// values are kept as they are.
+fn substitute(
+    text: &str,
+    params: &[Param],
+    values: &[String],
+    literals: &[Range<usize>],
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (at, _) in text.match_indices('%') {
+        if at < copied {
+            continue;
+        }
+        let rest = text.get(at + 1..).unwrap_or_default();
+        let Some((i, param)) = params.iter().enumerate().find(|(_, param)| {
+            rest.strip_prefix(param.key)
+                .is_some_and(|rest| rest.starts_with('%'))
+        }) else {
+            continue;
+        };
+        let value = values
+            .get(i)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .unwrap_or(param.default);
+        out.push_str(text.get(copied..at).unwrap_or_default());
+        if literals.iter().any(|literal| literal.contains(&at)) {
+            out.push_str(&value.replace('\\', "\\\\").replace('"', "\\\""));
+        } else {
+            out.push_str(value);
+        }
+        copied = at + param.key.len() + 2;
+    }
+    out.push_str(text.get(copied..).unwrap_or_default());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: [Param; 2] = [
+        Param {
+            key: "a",
+            prompt: "",
+            default: "A",
+        },
+        Param {
+            key: "b",
+            prompt: "",
+            default: "B",
+        },
+    ];
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| (*value).to_owned()).collect()
+    }
+
+    #[test]
+    fn expand_returns_the_stops() {
+        let (code, stops) = expand("${LIST}[${1}:${}] | ${unclosed");
+        assert_eq!(code, "LIST[1:] | ${unclosed");
+        assert_eq!(stops, [0..4, 5..6, 7..7, 21..21]);
+        let (code, stops) = expand("plain");
+        assert_eq!(code, "plain");
+        assert_eq!(stops.iter().collect::<Vec<_>>(), [&(5..5)]);
+    }
+
+    #[test]
+    fn every_snippet_has_a_stop() {
+        for snippet in &SNIPPETS {
+            let (code, stops) = expand(snippet.code);
+            assert!(!code.contains("${"), "{}", snippet.name);
+            assert!(stops.len() > 1, "{}", snippet.name);
+        }
+    }
+
+    #[test]
+    fn values_in_literals_are_escaped() {
+        let code = r#"<%a%> {{ "%a%" }}"#;
+        let filled = fill_code(code, &PARAMS, &values(&[r#"say "hi" \o/"#]));
+        assert_eq!(filled, r#"<say "hi" \o/> {{ "say \"hi\" \\o/" }}"#);
+    }
+
+    #[test]
+    fn values_outside_code_are_not_escaped() {
+        let filled = fill(r#""%a%""#, &PARAMS, &values(&[r#"x"\"#]));
+        assert_eq!(filled, r#""x"\""#);
+    }
+
+    #[test]
+    fn placeholders_in_values_are_kept() {
+        let filled = fill("%a% %b%", &PARAMS, &values(&["%b%", "b"]));
+        assert_eq!(filled, "%b% b");
+    }
+
+    #[test]
+    fn blank_or_missing_values_use_the_default() {
+        assert_eq!(fill("%a%%b%%c%", &PARAMS, &values(&["  "])), "AB%c%");
+        assert_eq!(fill("100% %a", &PARAMS, &[]), "100% %a");
+    }
+}
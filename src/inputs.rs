@@ -0,0 +1,414 @@
+//! JSON import and export of the playground inputs.
+//!
+//! The format is an object with the following keys:
+//!
+//! * `vars` - object mapping constant names to string values,
+//! * `arrays` - object mapping array names to arrays of strings,
+//! * `code` - optional string with the program,
+//! * `notes` - optional markdown string describing the program,
+//! * `run_config` - optional object with the settings that change how a run
+//!   behaves: `trailing_line`, `array_trailing_lines` and `keep_string_tabs`.
+//!
+//! The arrays hold the items the vm gets, see [`array_items`].
+//!
+//! Strict imports require `vars` and `arrays`, lenient ones leave the constants
+//! or arrays as they are when their key is missing.
+//!
+//! ```json
+//! {
+//!   "vars": { "name": "jon" },
+//!   "arrays": { "LIST": ["first", "second"] },
+//!   "code": "{{ \"Hello, $(name)\" }}",
+//!   "notes": "Greets `name`",
+//!   "run_config": {
+//!     "trailing_line": "Ignored",
+//!     "array_trailing_lines": { "LIST": "EmptyItem" },
+//!     "keep_string_tabs": false
+//!   }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Inputs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vars: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrays: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_config: Option<RunConfig>,
+}
+
+/// Settings that change how a run behaves.
+#[derive(Clone, Default, PartialEq, Eq, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RunConfig {
+    pub trailing_line: TrailingLine,
+    /// Arrays treating their blank last line unlike `trailing_line`.
+    pub array_trailing_lines: BTreeMap<String, TrailingLine>,
+    pub keep_string_tabs: bool,
+}
+
+impl RunConfig {
+    const KEYS: [&str; 3] = ["trailing_line", "array_trailing_lines", "keep_string_tabs"];
+
+    /// Like [`Inputs::from_json_strict`] for the `run_config` object, keeping
+    /// the default of every setting that is missing or invalid.
+    fn from_json_strict(config: serde_json::Map<String, Value>, errors: &mut Vec<String>) -> Self {
+        let mut run_config = Self::default();
+        for (key, value) in config {
+            match (key.as_str(), value) {
+                ("trailing_line", value) => {
+                    if let Some(trailing) =
+                        TrailingLine::from_json_strict(&value, "run_config.trailing_line", errors)
+                    {
+                        run_config.trailing_line = trailing;
+                    }
+                }
+                ("array_trailing_lines", Value::Object(arrays)) => {
+                    for (name, value) in arrays {
+                        let path = format!("run_config.array_trailing_lines.{name}");
+                        if let Some(trailing) =
+                            TrailingLine::from_json_strict(&value, &path, errors)
+                        {
+                            run_config.array_trailing_lines.insert(name, trailing);
+                        }
+                    }
+                }
+                ("array_trailing_lines", other) => errors.push(format!(
+                    "`run_config.array_trailing_lines` must be an object, found {}",
+                    kind(&other)
+                )),
+                ("keep_string_tabs", Value::Bool(keep)) => run_config.keep_string_tabs = keep,
+                ("keep_string_tabs", other) => errors.push(format!(
+                    "`run_config.keep_string_tabs` must be a boolean, found {}",
+                    kind(&other)
+                )),
+                _ => errors.push(format!(
+                    "unexpected key `run_config.{key}`, expected one of `{}`",
+                    Self::KEYS.join("`, `")
+                )),
+            }
+        }
+        run_config
+    }
+}
+
+/// What a blank last line of an array becomes, see [`array_items`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum TrailingLine {
+    /// The newline ending the last item is dropped: `a\nb\n` is the items `a`
+    /// and `b`, like `a\nb`, while `a\nb\n\n` ends with an empty item.
+    #[default]
+    Ignored,
+    /// Every line is an item: `a\nb\n` is the items `a`, `b` and an empty one.
+    EmptyItem,
+}
+
+impl TrailingLine {
+    pub const ALL: [Self; 2] = [Self::Ignored, Self::EmptyItem];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ignored => "ignored",
+            Self::EmptyItem => "an empty item",
+        }
+    }
+
+    fn from_json_strict(value: &Value, path: &str, errors: &mut Vec<String>) -> Option<Self> {
+        let parsed = serde_json::from_value(value.clone()).ok();
+        if parsed.is_none() {
+            let found = match value {
+                Value::String(s) => format!("`\"{s}\"`"),
+                other => kind(other).to_owned(),
+            };
+            errors.push(format!(
+                "`{path}` must be `\"Ignored\"` or `\"EmptyItem\"`, found {found}"
+            ));
+        }
+        parsed
+    }
+}
+
+/// Splits an array into the items the vm gets, one per line. An empty array has
+/// no items, a blank last line is an item only with [`TrailingLine::EmptyItem`].
+pub fn array_items(value: &str, trailing: TrailingLine) -> Vec<&str> {
+    if value.is_empty() {
+        return Vec::new();
+    }
+    let value = match trailing {
+        TrailingLine::Ignored => value.strip_suffix('\n').unwrap_or(value),
+        TrailingLine::EmptyItem => value,
+    };
+    value
+        .split('\n')
+        .map(|item| item.strip_suffix('\r').unwrap_or(item))
+        .collect()
+}
+
+/// Joins items into the text [`array_items`] splits back into them. With
+/// [`TrailingLine::EmptyItem`] a single empty item becomes an empty array.
+pub fn array_text(items: &[String], trailing: TrailingLine) -> String {
+    let mut text = items.join("\n");
+    if trailing == TrailingLine::Ignored && items.last().is_some_and(String::is_empty) {
+        text.push('\n');
+    }
+    text
+}
+
+impl Inputs {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Deserializes the inputs, ignoring unknown keys.
+    pub fn from_json(src: &str) -> Result<Self, Vec<String>> {
+        serde_json::from_str(src).map_err(|e| vec![e.to_string()])
+    }
+
+    /// Checks `src` against the documented format and reports every mismatch
+    /// with the path of the offending value.
+    pub fn from_json_strict(src: &str) -> Result<Self, Vec<String>> {
+        let value: Value = serde_json::from_str(src).map_err(|e| vec![e.to_string()])?;
+        let Value::Object(root) = value else {
+            return Err(vec![format!(
+                "expected an object at the top level, found {}",
+                kind(&value)
+            )]);
+        };
+        let mut inputs = Self::default();
+        let mut errors: Vec<String> = ["vars", "arrays"]
+            .into_iter()
+            .filter(|key| !root.contains_key(*key))
+            .map(|key| format!("missing key `{key}`"))
+            .collect();
+
+        for (key, value) in root {
+            match (key.as_str(), value) {
+                ("vars", Value::Object(vars)) => {
+                    let inputs = inputs.vars.insert(BTreeMap::new());
+                    for (name, value) in vars {
+                        match value {
+                            Value::String(s) => {
+                                inputs.insert(name, s);
+                            }
+                            other => errors.push(format!(
+                                "`vars.{name}` must be a string, found {}",
+                                kind(&other)
+                            )),
+                        }
+                    }
+                }
+                ("arrays", Value::Object(arrays)) => {
+                    let inputs = inputs.arrays.insert(BTreeMap::new());
+                    for (name, value) in arrays {
+                        let Value::Array(items) = value else {
+                            errors.push(format!(
+                                "`arrays.{name}` must be an array of strings, found {}",
+                                kind(&value)
+                            ));
+                            continue;
+                        };
+                        let mut strings = Vec::with_capacity(items.len());
+                        for (i, item) in items.into_iter().enumerate() {
+                            match item {
+                                Value::String(s) => strings.push(s),
+                                other => errors.push(format!(
+                                    "`arrays.{name}[{i}]` must be a string, found {}",
+                                    kind(&other)
+                                )),
+                            }
+                        }
+                        inputs.insert(name, strings);
+                    }
+                }
+                ("run_config", Value::Object(config)) => {
+                    inputs.run_config = Some(RunConfig::from_json_strict(config, &mut errors));
+                }
+                ("code", Value::String(code)) => inputs.code = Some(code),
+                ("notes", Value::String(notes)) => inputs.notes = Some(notes),
+                ("vars" | "arrays" | "run_config", other) => {
+                    errors.push(format!("`{key}` must be an object, found {}", kind(&other)));
+                }
+                ("code" | "notes", other) => {
+                    errors.push(format!("`{key}` must be a string, found {}", kind(&other)));
+                }
+                _ => errors.push(format!(
+                    "unexpected key `{key}`, expected one of `vars`, `arrays`, `code`, `notes`, `run_config`"
+                )),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(inputs)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_accepts_the_documented_format() {
+        let src = r#"{
+            "vars": { "name": "jon" },
+            "arrays": { "LIST": ["first", ""] },
+            "code": "{{ LIST[:] }}",
+            "notes": "Lists"
+        }"#;
+        let inputs = Inputs::from_json_strict(src).unwrap_or_default();
+        assert_eq!(
+            inputs
+                .vars
+                .unwrap_or_default()
+                .get("name")
+                .map(String::as_str),
+            Some("jon")
+        );
+        assert_eq!(
+            inputs.arrays.unwrap_or_default().get("LIST"),
+            Some(&vec!["first".into(), String::new()])
+        );
+        assert_eq!(inputs.code.as_deref(), Some("{{ LIST[:] }}"));
+        assert_eq!(inputs.notes.as_deref(), Some("Lists"));
+        assert!(inputs.run_config.is_none());
+    }
+
+    #[test]
+    fn strict_requires_vars_and_arrays() {
+        for src in ["{}", r#"{ "code": "" }"#] {
+            let errors = Inputs::from_json_strict(src).err().unwrap_or_default();
+            assert_eq!(errors, ["missing key `vars`", "missing key `arrays`"]);
+        }
+    }
+
+    #[test]
+    fn strict_reports_every_mismatch() {
+        let src = r#"{
+            "vars": { "a": 1 },
+            "arrays": { "L": ["x", 2], "M": "y" },
+            "code": [],
+            "extra": null
+        }"#;
+        let errors = Inputs::from_json_strict(src).err().unwrap_or_default();
+        assert_eq!(
+            errors,
+            [
+                "`arrays.L[1]` must be a string, found a number",
+                "`arrays.M` must be an array of strings, found a string",
+                "`code` must be a string, found an array",
+                "unexpected key `extra`, expected one of `vars`, `arrays`, `code`, `notes`, `run_config`",
+                "`vars.a` must be a string, found a number",
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_rejects_other_top_level_values() {
+        let errors = Inputs::from_json_strict("[]").err().unwrap_or_default();
+        assert_eq!(
+            errors,
+            ["expected an object at the top level, found an array"]
+        );
+        assert!(Inputs::from_json_strict("{").is_err());
+    }
+
+    #[test]
+    fn lenient_leaves_missing_maps_out() {
+        let inputs = Inputs::from_json(r#"{ "code": "x", "other": 1 }"#).unwrap_or_default();
+        assert!(inputs.vars.is_none());
+        assert!(inputs.arrays.is_none());
+        assert_eq!(inputs.code.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn run_config_round_trips() {
+        let inputs = Inputs {
+            run_config: Some(RunConfig {
+                trailing_line: TrailingLine::EmptyItem,
+                array_trailing_lines: BTreeMap::from([("L".to_owned(), TrailingLine::Ignored)]),
+                keep_string_tabs: true,
+            }),
+            ..Inputs::default()
+        };
+        let json = inputs
+            .to_json()
+            .replacen('{', r#"{ "vars": {}, "arrays": {},"#, 1);
+        let imported = Inputs::from_json_strict(&json).unwrap_or_default();
+        assert_eq!(imported.run_config, inputs.run_config);
+        let lenient = Inputs::from_json(&inputs.to_json()).unwrap_or_default();
+        assert_eq!(lenient.run_config, inputs.run_config);
+    }
+
+    #[test]
+    fn strict_reports_run_config_mismatches() {
+        let src = r#"{
+            "vars": {},
+            "arrays": {},
+            "run_config": {
+                "trailing_line": "Dropped",
+                "array_trailing_lines": { "L": 1, "M": "EmptyItem" },
+                "keep_string_tabs": "yes",
+                "seed": 1
+            }
+        }"#;
+        let errors = Inputs::from_json_strict(src).err().unwrap_or_default();
+        assert_eq!(
+            errors,
+            [
+                "`run_config.array_trailing_lines.L` must be `\"Ignored\"` or `\"EmptyItem\"`, found a number",
+                "`run_config.keep_string_tabs` must be a boolean, found a string",
+                "unexpected key `run_config.seed`, expected one of \
+                 `trailing_line`, `array_trailing_lines`, `keep_string_tabs`",
+                "`run_config.trailing_line` must be `\"Ignored\"` or `\"EmptyItem\"`, found `\"Dropped\"`",
+            ]
+        );
+    }
+
+    #[test]
+    fn array_items_by_trailing_line() {
+        use TrailingLine::{EmptyItem, Ignored};
+        assert!(array_items("", Ignored).is_empty());
+        assert!(array_items("", EmptyItem).is_empty());
+        assert_eq!(array_items("a\nb", Ignored), ["a", "b"]);
+        assert_eq!(array_items("a\r\nb\r\n", Ignored), ["a", "b"]);
+        assert_eq!(array_items("a\n\n", Ignored), ["a", ""]);
+        assert_eq!(array_items("\n", Ignored), [""]);
+        assert_eq!(array_items("a\n", EmptyItem), ["a", ""]);
+        assert_eq!(array_items("a\n\n", EmptyItem), ["a", "", ""]);
+    }
+
+    #[test]
+    fn array_text_splits_back_into_the_items() {
+        let lists: [&[&str]; 4] = [&[], &["a"], &["a", ""], &["", "b"]];
+        for trailing in TrailingLine::ALL {
+            for items in lists {
+                let items: Vec<String> = items.iter().map(|item| (*item).to_owned()).collect();
+                assert_eq!(array_items(&array_text(&items, trailing), trailing), items);
+            }
+        }
+        assert_eq!(array_text(&[String::new()], TrailingLine::Ignored), "\n");
+    }
+}
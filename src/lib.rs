@@ -1,4 +1,15 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod action_log;
+mod analysis;
 mod app;
+#[cfg(target_arch = "wasm32")]
+mod clipboard;
+mod diff;
+mod dotenv;
+mod examples;
+mod inputs;
+mod markdown;
+mod output;
+mod validation;
 pub use app::App;
@@ -0,0 +1,161 @@
+//! A small subset of markdown, enough for notes on a program:
+//!
+//! * `#` to `######` headings,
+//! * `-` and `*` list items,
+//! * fenced code blocks,
+//! * paragraphs with `` `code` ``, `**strong**` and `*emphasized*` text.
+//!
+//! Anything else is shown as plain text.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Block {
+    Heading(usize, Vec<Span>),
+    Item(Vec<Span>),
+    Code(String),
+    Paragraph(Vec<Span>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Span {
+    Text(String),
+    Strong(String),
+    Emphasis(String),
+    Code(String),
+}
+
+pub fn parse(src: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = src.lines();
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(spans(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut blocks);
+            let code: Vec<&str> = lines
+                .by_ref()
+                .take_while(|line| !line.trim().starts_with("```"))
+                .collect();
+            blocks.push(Block::Code(code.join("\n")));
+        } else if let Some((level, title)) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading(level, spans(title)));
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Item(spans(item.trim())));
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+/// Level and text of a `# Heading` line.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let title = line.get(level..)?.strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| (level, title.trim()))
+}
+
+/// Splits a line of text at its inline markup. Unclosed markup is kept as text.
+fn spans(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (marker, wrap): (&str, fn(String) -> Span) = match c {
+            '`' => ("`", Span::Code),
+            '*' if rest.starts_with("**") => ("**", Span::Strong),
+            '*' => ("*", Span::Emphasis),
+            _ => {
+                plain.push(c);
+                rest = rest.get(c.len_utf8()..).unwrap_or_default();
+                continue;
+            }
+        };
+        let inner = rest.get(marker.len()..).unwrap_or_default();
+        if let Some(end) = inner.find(marker).filter(|end| *end > 0) {
+            if !plain.is_empty() {
+                spans.push(Span::Text(std::mem::take(&mut plain)));
+            }
+            spans.push(wrap(inner.get(..end).unwrap_or_default().to_owned()));
+            rest = inner.get(end + marker.len()..).unwrap_or_default();
+        } else {
+            plain.push_str(marker);
+            rest = inner;
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::Text(plain));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Span {
+        Span::Text(text.into())
+    }
+
+    #[test]
+    fn blocks() {
+        let src = "# Title\nfirst\nline\n\n- item\n* `code`\n```\nlet x;\n  y\n```\n####### not a heading";
+        assert_eq!(
+            parse(src),
+            [
+                Block::Heading(1, vec![text("Title")]),
+                Block::Paragraph(vec![text("first line")]),
+                Block::Item(vec![text("item")]),
+                Block::Item(vec![Span::Code("code".into())]),
+                Block::Code("let x;\n  y".into()),
+                Block::Paragraph(vec![text("####### not a heading")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_markup() {
+        assert_eq!(
+            parse("a **b** *c* `d*e` snake_case_name"),
+            [Block::Paragraph(vec![
+                text("a "),
+                Span::Strong("b".into()),
+                text(" "),
+                Span::Emphasis("c".into()),
+                text(" "),
+                Span::Code("d*e".into()),
+                text(" snake_case_name"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn unclosed_markup_is_text() {
+        assert_eq!(
+            parse("2 * 3 and `x"),
+            [Block::Paragraph(vec![text("2 * 3 and `x")])]
+        );
+        assert_eq!(
+            parse("#nospace"),
+            [Block::Paragraph(vec![text("#nospace")])]
+        );
+    }
+
+    #[test]
+    fn unclosed_code_block_runs_to_the_end() {
+        assert_eq!(parse("```\na\nb"), [Block::Code("a\nb".into())]);
+    }
+}
@@ -0,0 +1,273 @@
+//! Post-processing of the generated output. Applied only to what is shown,
+//! the output produced by the vm is kept as is.
+
+use std::borrow::Cow;
+
+/// Hard-wraps every line of `text` at `width` characters, breaking only at spaces.
+/// Words longer than `width` are left intact on their own line.
+pub fn wrap(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut column = 0;
+        // leading indentation is kept together with the first word
+        let mut has_word = false;
+        for (j, word) in line.split(' ').enumerate() {
+            let len = word.chars().count();
+            if j == 0 {
+                column = len;
+            } else if has_word && !word.is_empty() && column + 1 + len > width {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push('\n');
+                column = len;
+            } else {
+                out.push(' ');
+                column += 1 + len;
+            }
+            has_word |= !word.is_empty();
+            out.push_str(word);
+        }
+    }
+    out
+}
+
+/// Splits every non-empty line of `text` at the first `separator` into a trimmed
+/// key and value. Returns `None` unless all such lines contain the separator.
+pub fn key_values<'a>(text: &'a str, separator: &str) -> Option<Vec<(&'a str, &'a str)>> {
+    if separator.is_empty() {
+        return None;
+    }
+    let pairs: Option<Vec<_>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split_once(separator)
+                .map(|(key, value)| (key.trim(), value.trim()))
+        })
+        .collect();
+    pairs.filter(|pairs| !pairs.is_empty())
+}
+
+/// Format of the generated text, guessed from its contents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Xml,
+    Json,
+    Text,
+}
+
+impl Format {
+    pub fn sniff(text: &str) -> Self {
+        let text = text.trim_start();
+        let starts_with = |prefix: &str| {
+            text.get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        };
+        if starts_with("<?xml") {
+            Self::Xml
+        } else if starts_with("<!doctype html")
+            || starts_with("<html")
+            || (text.starts_with('<') && text.contains("</"))
+        {
+            Self::Html
+        } else if (text.starts_with('{') || text.starts_with('['))
+            && serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok()
+        {
+            Self::Json
+        } else {
+            Self::Text
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Html => "HTML",
+            Self::Xml => "XML",
+            Self::Json => "JSON",
+            Self::Text => "Text",
+        }
+    }
+}
+
+/// Removes insignificant whitespace from HTML, XML and JSON, other formats are
+/// returned as they are.
+pub fn minify(text: &str, format: Format) -> Cow<'_, str> {
+    match format {
+        Format::Html | Format::Xml => Cow::Owned(minify_markup(text)),
+        Format::Json => Cow::Owned(minify_json(text)),
+        Format::Text => Cow::Borrowed(text),
+    }
+}
+
+/// Drops all whitespace outside of strings.
+fn minify_json(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c.is_whitespace() {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Elements whose contents are whitespace sensitive and copied as is.
+const VERBATIM: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Collapses whitespace runs to a single space, keeping quoted attribute values
+/// and [`VERBATIM`] elements. Runs between tags are dropped if they span lines,
+/// as indentation is, but kept as a space otherwise since they separate inline elements.
+fn minify_markup(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut in_tag = false;
+    let mut quote = None;
+    while let Some(c) = rest.chars().next() {
+        if !in_tag
+            && c == '<'
+            && let Some(tag) = VERBATIM.iter().find(|tag| opens(rest, tag))
+        {
+            // copied up to the name of the closing tag, whose `>` ends the tag as usual
+            let close = format!("</{tag}");
+            let found = rest.to_ascii_lowercase().find(&close);
+            let end = found.map_or(rest.len(), |i| i + close.len());
+            out.push_str(rest.get(..end).unwrap_or(rest));
+            rest = rest.get(end..).unwrap_or_default();
+            in_tag = found.is_some();
+            continue;
+        }
+        if c.is_whitespace() && quote.is_none() {
+            let run = rest.len() - rest.trim_start().len();
+            let (space, after) = rest.split_at(run);
+            rest = after;
+            let previous = out.chars().next_back();
+            let next = rest.chars().next();
+            let between_tags = previous == Some('>') && next == Some('<');
+            if in_tag
+                || !(previous.is_none() || next.is_none() || (between_tags && space.contains('\n')))
+            {
+                out.push(' ');
+            }
+            continue;
+        }
+        match c {
+            '<' if quote.is_none() => in_tag = true,
+            '>' if quote.is_none() => in_tag = false,
+            '"' | '\'' if in_tag => {
+                quote = match quote {
+                    None => Some(c),
+                    Some(open) if open == c => None,
+                    open => open,
+                };
+            }
+            _ => {}
+        }
+        out.push(c);
+        rest = rest.get(c.len_utf8()..).unwrap_or_default();
+    }
+    out
+}
+
+/// Whether `text` starts with an opening `tag`, e.g. `<pre>` or `<pre class="x">`.
+fn opens(text: &str, tag: &str) -> bool {
+    text.get(1..=tag.len())
+        .is_some_and(|name| name.eq_ignore_ascii_case(tag))
+        && text
+            .get(tag.len() + 1..)
+            .and_then(|after| after.chars().next())
+            .is_some_and(|c| c == '>' || c == '/' || c.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_at_spaces() {
+        assert_eq!(wrap("one two three four", 9), "one two\nthree\nfour");
+        assert_eq!(wrap("a verylongword b", 4), "a\nverylongword\nb");
+        assert_eq!(
+            wrap("  indented text\n\nnext", 10),
+            "  indented\ntext\n\nnext"
+        );
+    }
+
+    #[test]
+    fn key_values_need_a_separator_on_every_line() {
+        assert_eq!(
+            key_values("a: 1\n\nb :2", ":"),
+            Some(vec![("a", "1"), ("b", "2")])
+        );
+        assert_eq!(key_values("a: 1\nb", ":"), None);
+        assert_eq!(key_values("", ":"), None);
+        assert_eq!(key_values("a: 1", ""), None);
+    }
+
+    #[test]
+    fn sniff_formats() {
+        assert_eq!(
+            Format::sniff("  <!DOCTYPE html><html></html>"),
+            Format::Html
+        );
+        assert_eq!(Format::sniff("<p>x</p>"), Format::Html);
+        assert_eq!(Format::sniff("<?xml version=\"1.0\"?><a/>"), Format::Xml);
+        assert_eq!(Format::sniff("{ \"a\": [1] }"), Format::Json);
+        assert_eq!(Format::sniff("{ not json"), Format::Text);
+        assert_eq!(Format::sniff("a < b"), Format::Text);
+    }
+
+    #[test]
+    fn minify_json_keeps_strings() {
+        assert_eq!(
+            minify("{\n  \"a b\": [1, \"\\\" x\"]\n}", Format::Json),
+            "{\"a b\":[1,\"\\\" x\"]}"
+        );
+    }
+
+    #[test]
+    fn minify_markup_collapses_whitespace() {
+        let html =
+            "<ul>\n  <li>one   two</li>\n  <li title=\"a  b\">x</li>\n</ul>\n<b>a</b> <i>b</i>";
+        assert_eq!(
+            minify(html, Format::Html),
+            "<ul><li>one two</li><li title=\"a  b\">x</li></ul><b>a</b> <i>b</i>"
+        );
+    }
+
+    #[test]
+    fn minify_markup_keeps_verbatim_elements() {
+        let html = "<div>\n  <pre class=\"x\">  a\n   b </pre>\n  <PRE>\n c</pre>\n</div>";
+        assert_eq!(
+            minify(html, Format::Html),
+            "<div><pre class=\"x\">  a\n   b </pre><PRE>\n c</pre></div>"
+        );
+        assert_eq!(
+            minify("<pre> never closed", Format::Html),
+            "<pre> never closed"
+        );
+    }
+
+    #[test]
+    fn minify_leaves_text_alone() {
+        assert!(matches!(
+            minify(" a  b ", Format::Text),
+            Cow::Borrowed(" a  b ")
+        ));
+    }
+}
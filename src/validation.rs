@@ -0,0 +1,94 @@
+//! Rules the value of a constant is checked against before running.
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Rule {
+    NonEmpty,
+    Integer,
+    Number,
+    /// Matches the pattern anywhere in the value unless it is anchored.
+    Regex(String),
+}
+
+impl Rule {
+    /// Rules offered in the editor, the regex one with an empty pattern.
+    pub fn builtin() -> [Self; 4] {
+        [
+            Self::NonEmpty,
+            Self::Integer,
+            Self::Number,
+            Self::Regex(String::new()),
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NonEmpty => "Non-empty",
+            Self::Integer => "Integer",
+            Self::Number => "Number",
+            Self::Regex(_) => "Regex",
+        }
+    }
+
+    /// Returns why `value` breaks the rule, if it does.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let valid = match self {
+            Self::NonEmpty => !value.trim().is_empty(),
+            Self::Integer => value.trim().parse::<i64>().is_ok(),
+            Self::Number => value.trim().parse::<f64>().is_ok_and(f64::is_finite),
+            Self::Regex(pattern) => regex::Regex::new(pattern)
+                .map_err(|e| format!("invalid pattern: {e}"))?
+                .is_match(value),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(match self {
+                Self::NonEmpty => "must not be empty".into(),
+                Self::Integer => "must be an integer".into(),
+                Self::Number => "must be a number".into(),
+                Self::Regex(pattern) => format!("must match `{pattern}`"),
+            })
+        }
+    }
+
+    /// Whether `other` is the same kind of rule, ignoring the pattern.
+    pub fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_rules() {
+        assert!(Rule::NonEmpty.check(" x ").is_ok());
+        assert_eq!(Rule::NonEmpty.check("  "), Err("must not be empty".into()));
+        assert!(Rule::Integer.check(" -12 ").is_ok());
+        assert!(Rule::Integer.check("1.5").is_err());
+        assert!(Rule::Number.check("1.5e3").is_ok());
+        assert!(Rule::Number.check("inf").is_err());
+        assert!(Rule::Number.check("NaN").is_err());
+    }
+
+    #[test]
+    fn regex_rule() {
+        assert!(Rule::Regex("b+".into()).check("abbc").is_ok());
+        assert_eq!(
+            Rule::Regex("^b".into()).check("ab"),
+            Err("must match `^b`".into())
+        );
+        assert!(
+            Rule::Regex("(".into())
+                .check("x")
+                .is_err_and(|e| e.starts_with("invalid pattern"))
+        );
+    }
+
+    #[test]
+    fn same_kind_ignores_the_pattern() {
+        assert!(Rule::Regex("a".into()).same_kind(&Rule::Regex(String::new())));
+        assert!(!Rule::Integer.same_kind(&Rule::Number));
+    }
+}